@@ -0,0 +1,6 @@
+//! Alias for `rust-issue-stats first-response`.
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    rust_issue_stats::cli::alias_main("first-response").await
+}
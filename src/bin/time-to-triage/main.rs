@@ -0,0 +1,6 @@
+//! Alias for `rust-issue-stats triage`.
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    rust_issue_stats::cli::alias_main("triage").await
+}
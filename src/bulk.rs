@@ -0,0 +1,300 @@
+//! Bulk triage: commenting on and closing issues through GraphQL mutations.
+//! Nothing is changed unless [`BulkTriage::with_apply`] is used; a dry run only
+//! prints the planned mutations.
+
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+use std::io::Write;
+use std::path::PathBuf;
+
+use crate::{queries, GitHub, IssueWithTimelineItems, PagedLabels};
+
+/// What to do with each issue.
+#[derive(Debug, Clone, Default)]
+pub struct TriageActions {
+    /// Comment with this text after [`render_template`].
+    pub comment: Option<String>,
+    /// Close the issue with reason `NOT_PLANNED`.
+    pub close: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "mutation")]
+pub enum Mutation {
+    #[serde(rename = "addComment")]
+    AddComment { body: String },
+    /// Closes with reason `NOT_PLANNED`.
+    #[serde(rename = "closeIssue")]
+    CloseIssue,
+}
+
+/// Applies [`TriageActions`] to at most `max_issues` issues.
+pub struct BulkTriage {
+    owner: String,
+    name: String,
+    actions: TriageActions,
+    max_issues: usize,
+    handled_issues: usize,
+    /// Issues without this label when they are about to be changed are skipped.
+    required_label: Option<String>,
+    /// `None` for a dry run.
+    apply: Option<Apply>,
+}
+
+struct Apply {
+    github: GitHub,
+    audit_log: PathBuf,
+}
+
+/// An issue as it is right before mutating it, which may differ from the data
+/// it was listed by.
+#[derive(Deserialize)]
+struct CurrentIssue {
+    id: String,
+    state: String,
+    labels: PagedLabels,
+}
+
+/// One line of the audit log.
+#[derive(Serialize)]
+struct AuditEntry<'a> {
+    timestamp: DateTime<Utc>,
+    repository: String,
+    url: &'a str,
+    #[serde(flatten)]
+    mutation: &'a Mutation,
+    /// `ok`, or the error.
+    result: String,
+}
+
+impl BulkTriage {
+    pub fn dry_run(owner: &str, name: &str, actions: TriageActions, max_issues: usize) -> Self {
+        Self {
+            owner: owner.to_owned(),
+            name: name.to_owned(),
+            actions,
+            max_issues,
+            handled_issues: 0,
+            required_label: None,
+            apply: None,
+        }
+    }
+
+    /// Skip issues that no longer have `label` when they are about to be
+    /// changed.
+    pub fn with_required_label(mut self, label: &str) -> Self {
+        self.required_label = Some(label.to_owned());
+        self
+    }
+
+    /// Really make the mutations, and append each one and its result to
+    /// `audit_log` as a JSON object per line.
+    pub fn with_apply(mut self, github: GitHub, audit_log: PathBuf) -> Self {
+        self.apply = Some(Apply { github, audit_log });
+        self
+    }
+
+    /// The mutations for an issue. `values` fill in the comment template.
+    pub fn mutations(&self, values: &[(&str, String)]) -> Vec<Mutation> {
+        let mut mutations = vec![];
+        if let Some(template) = &self.actions.comment {
+            mutations.push(Mutation::AddComment {
+                body: render_template(template, values),
+            });
+        }
+        if self.actions.close {
+            mutations.push(Mutation::CloseIssue);
+        }
+        mutations
+    }
+
+    /// Prints or makes the mutations for `issue`. Returns `Ok(false)` without
+    /// doing anything once `max_issues` issues have been handled. The first
+    /// failed mutation stops the run.
+    ///
+    /// Before making mutations, the issue is fetched again, and skipped unless
+    /// it is still open and has the required label. Skipped issues do not
+    /// count towards `max_issues`.
+    pub async fn handle(
+        &mut self,
+        issue: &IssueWithTimelineItems,
+        values: &[(&str, String)],
+    ) -> anyhow::Result<bool> {
+        if self.handled_issues >= self.max_issues {
+            return Ok(false);
+        }
+        let issue_id = match &self.apply {
+            Some(apply) => match self.current_issue_id(apply, issue).await? {
+                Some(issue_id) => Some(issue_id),
+                None => return Ok(true),
+            },
+            None => None,
+        };
+        self.handled_issues += 1;
+
+        let mutations = self.mutations(values);
+        let (Some(apply), Some(issue_id)) = (&self.apply, issue_id) else {
+            for mutation in &mutations {
+                eprintln!("Would {mutation} on {}", issue.url);
+            }
+            return Ok(true);
+        };
+
+        for mutation in &mutations {
+            let result = apply.github.mutate(mutation, &issue_id).await;
+            self.audit(issue, mutation, &result)?;
+            result.with_context(|| format!("failed to {mutation} on {}", issue.url))?;
+            eprintln!("Did {mutation} on {}", issue.url);
+        }
+        Ok(true)
+    }
+
+    /// The ID of `issue` if it is still open and has the required label, since
+    /// it may have changed after the data it was listed by was fetched.
+    async fn current_issue_id(
+        &self,
+        apply: &Apply,
+        issue: &IssueWithTimelineItems,
+    ) -> anyhow::Result<Option<String>> {
+        let current = apply
+            .github
+            .current_issue(&self.owner, &self.name, issue.number)
+            .await
+            .with_context(|| format!("failed to look up {}", issue.url))?;
+        if current.state != "OPEN" {
+            eprintln!("Skipping {}, it is no longer open", issue.url);
+            return Ok(None);
+        }
+        if let Some(label) = &self.required_label {
+            // Issues with more than 100 labels are skipped if the label is not
+            // among the first ones, which errs on the side of doing nothing.
+            if !current.labels.nodes.iter().any(|l| l.name == *label) {
+                eprintln!("Skipping {}, it is no longer labeled {label}", issue.url);
+                return Ok(None);
+            }
+        }
+        Ok(Some(current.id))
+    }
+
+    fn audit(
+        &self,
+        issue: &IssueWithTimelineItems,
+        mutation: &Mutation,
+        result: &anyhow::Result<()>,
+    ) -> anyhow::Result<()> {
+        let Some(apply) = &self.apply else {
+            return Ok(());
+        };
+        let entry = AuditEntry {
+            timestamp: Utc::now(),
+            repository: format!("{}/{}", self.owner, self.name),
+            url: &issue.url,
+            mutation,
+            result: match result {
+                Ok(()) => "ok".to_owned(),
+                Err(e) => format!("{e:#}"),
+            },
+        };
+
+        let path = &apply.audit_log;
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir)?;
+        }
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("failed to open audit log {}", path.display()))?;
+        writeln!(file, "{}", serde_json::to_string(&entry)?)?;
+        Ok(())
+    }
+}
+
+impl GitHub {
+    /// The GraphQL node ID that mutations need, and the state and labels of
+    /// the issue right now.
+    async fn current_issue(
+        &self,
+        owner: &str,
+        name: &str,
+        number: u32,
+    ) -> anyhow::Result<CurrentIssue> {
+        let mut data = self
+            .query_uncached(
+                queries::CURRENT_ISSUE_QUERY,
+                serde_json::json!({
+                    "owner": owner,
+                    "name": name,
+                    "number": number,
+                }),
+            )
+            .await?;
+        serde_json::from_value(data["repository"]["issue"].take()).context("no issue in response")
+    }
+
+    async fn mutate(&self, mutation: &Mutation, issue_id: &str) -> anyhow::Result<()> {
+        let (query, variables) = match mutation {
+            Mutation::AddComment { body } => (
+                queries::ADD_COMMENT_MUTATION,
+                serde_json::json!({ "subjectId": issue_id, "body": body }),
+            ),
+            Mutation::CloseIssue => (
+                queries::CLOSE_ISSUE_MUTATION,
+                serde_json::json!({ "issueId": issue_id }),
+            ),
+        };
+        self.query_uncached(query, variables).await?;
+        Ok(())
+    }
+}
+
+/// Replaces each `{key}` in `template` with its value. Other braces are kept.
+pub fn render_template(template: &str, values: &[(&str, String)]) -> String {
+    values
+        .iter()
+        .fold(template.to_owned(), |text, (key, value)| {
+            text.replace(&format!("{{{key}}}"), value)
+        })
+}
+
+impl Display for Mutation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Mutation::AddComment { body } => write!(f, "addComment {body:?}"),
+            Mutation::CloseIssue => write!(f, "closeIssue as NOT_PLANNED"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mutations() {
+        let triage = BulkTriage::dry_run(
+            "rust-lang",
+            "rust",
+            TriageActions {
+                comment: Some("Closing {url} after {label_months} months. {unknown}".to_owned()),
+                close: true,
+            },
+            1,
+        );
+        assert_eq!(
+            triage.mutations(&[
+                ("url", "https://github.com/rust-lang/rust/issues/1".to_owned()),
+                ("label_months", "40".to_owned()),
+            ]),
+            vec![
+                Mutation::AddComment {
+                    body: "Closing https://github.com/rust-lang/rust/issues/1 after 40 months. {unknown}"
+                        .to_owned()
+                },
+                Mutation::CloseIssue,
+            ]
+        );
+    }
+}
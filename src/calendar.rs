@@ -0,0 +1,73 @@
+//! Calendar arithmetic for ages, since months and years differ in length.
+
+use chrono::{DateTime, Datelike, FixedOffset, Months, NaiveDate};
+
+/// The number of whole calendar months from `from` to `to`, like an age. A
+/// month after January 31 is the last day of February. Negative if `to` is
+/// before `from`.
+pub fn months_between(from: DateTime<FixedOffset>, to: DateTime<FixedOffset>) -> i64 {
+    if to < from {
+        return -months_between(to, from);
+    }
+
+    let months = (to.year() as i64 * 12 + to.month0() as i64)
+        - (from.year() as i64 * 12 + from.month0() as i64);
+    let reached = |months: i64| {
+        from.checked_add_months(Months::new(months as u32))
+            .is_some_and(|date| date <= to)
+    };
+    if reached(months) {
+        months
+    } else {
+        months - 1
+    }
+}
+
+/// Parses an RFC 3339 timestamp, or a date which means midnight UTC.
+pub fn parse_timestamp(s: &str) -> Result<DateTime<FixedOffset>, String> {
+    DateTime::parse_from_rfc3339(s)
+        .or_else(|_| {
+            s.parse::<NaiveDate>().map(|date| {
+                date.and_hms_opt(0, 0, 0)
+                    .expect("midnight exists")
+                    .and_utc()
+                    .fixed_offset()
+            })
+        })
+        .map_err(|_| {
+            format!("expected a date like `2022-06-01` or an RFC 3339 timestamp, got `{s}`")
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(s: &str) -> DateTime<FixedOffset> {
+        parse_timestamp(s).unwrap()
+    }
+
+    #[test]
+    fn test_months_between() {
+        assert_eq!(months_between(at("2020-01-15"), at("2020-02-14")), 0);
+        assert_eq!(months_between(at("2020-01-15"), at("2020-02-15")), 1);
+        assert_eq!(months_between(at("2020-01-31"), at("2020-02-29")), 1);
+        assert_eq!(months_between(at("2020-01-31"), at("2020-02-28")), 0);
+        assert_eq!(months_between(at("2020-03-01"), at("2023-03-01")), 36);
+        assert_eq!(
+            months_between(at("2020-03-01T12:00:00Z"), at("2023-03-01T11:00:00Z")),
+            35
+        );
+        assert_eq!(months_between(at("2020-02-15"), at("2020-01-15")), -1);
+
+        // 36 months is 1096 days here, so `days / 30` would say 36 months
+        // already after 1080 days.
+        assert_eq!(months_between(at("2020-01-01"), at("2022-12-16")), 35);
+    }
+
+    #[test]
+    fn test_parse_timestamp() {
+        assert_eq!(at("2022-06-01"), at("2022-06-01T00:00:00Z"));
+        assert!(parse_timestamp("June").is_err());
+    }
+}
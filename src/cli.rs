@@ -0,0 +1,635 @@
+//! The `rust-issue-stats` command line interface. The binaries in `src/bin/`
+//! are aliases for its subcommands, kept for compatibility. Options that are
+//! not given on the command line can be set in a [`Config`] file.
+
+use anyhow::Context;
+use chrono::{DateTime, FixedOffset, NaiveDate, Utc};
+use clap::parser::ValueSource;
+use clap::CommandFactory;
+use std::ffi::OsString;
+use std::fmt::Display;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::commands::*;
+use crate::{
+    log_init, parse_timestamp, Config, GitHub, IssueSource, IssuesQuery, Output, OutputFormat,
+    ProgressDisplay,
+};
+
+/// Statistics about issues in GitHub repositories, in particular rust-lang/rust.
+#[derive(clap::Parser, Debug)]
+#[command(name = "rust-issue-stats", version)]
+pub struct Cli {
+    #[command(flatten)]
+    pub global: GlobalArgs,
+
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+/// Options shared by all subcommands. They can be given before or after the
+/// subcommand.
+#[derive(clap::Args, Debug)]
+#[command(next_help_heading = "Global options")]
+pub struct GlobalArgs {
+    /// The configuration file. Defaults to `rust-issue-stats.toml` if it
+    /// exists.
+    #[arg(long, global = true)]
+    pub config: Option<PathBuf>,
+
+    /// The repository to analyze issues of, as `owner/name` or a short name
+    /// from the configuration file.
+    #[arg(long, global = true, default_value = "rust-lang/rust")]
+    pub repo: String,
+
+    /// Where to get issues from: `github`, or the path to a JSONL data dump
+    /// that contains the timeline items the subcommand needs.
+    #[arg(long, global = true, default_value = "github")]
+    pub source: Source,
+
+    /// Store GitHub API responses in this directory and reuse them on later
+    /// runs instead of making the same requests again.
+    #[arg(long, global = true)]
+    pub cache: Option<PathBuf>,
+
+    /// The GitHub token. `--token-file`, `GITHUB_TOKEN` or `gh auth login`
+    /// keep it out of the process list.
+    #[arg(long, global = true)]
+    pub token: Option<String>,
+
+    /// A file that contains the GitHub token.
+    #[arg(long, global = true)]
+    pub token_file: Option<PathBuf>,
+
+    /// The GitHub API endpoint, e.g. `https://github.example.com/api` for
+    /// GitHub Enterprise. Defaults to `GITHUB_API_URL` or
+    /// `https://api.github.com`.
+    #[arg(long, global = true)]
+    pub api_url: Option<String>,
+
+    /// Sent as the user agent of GitHub API requests.
+    #[arg(long, global = true)]
+    pub user_agent: Option<String>,
+
+    /// Seconds after which a GitHub API request fails.
+    #[arg(long, global = true)]
+    pub timeout: Option<u64>,
+
+    /// Only estimate how many GitHub API requests and rate limit points the
+    /// subcommand needs, with one cheap request.
+    #[arg(long, global = true)]
+    pub plan: bool,
+
+    /// Stop after this many GitHub API requests. The results are partial
+    /// then.
+    #[arg(long, global = true)]
+    pub max_requests: Option<u64>,
+
+    /// Stop before spending more than this many GitHub API rate limit points.
+    /// The results are partial then.
+    #[arg(long, global = true)]
+    pub max_cost: Option<u64>,
+
+    /// Show issues processed, pages, requests and the ETA on stderr. `auto`
+    /// shows it if stderr is a terminal.
+    #[arg(long, global = true, value_enum, default_value_t)]
+    pub progress: ProgressMode,
+
+    #[arg(long, global = true, value_enum, default_value_t)]
+    pub format: OutputFormat,
+
+    #[arg(long, global = true, default_value = "10")]
+    pub page_size: u16,
+
+    #[arg(long, global = true, default_value = "2")]
+    pub pages: usize,
+
+    /// Only analyze issues created on or after this date, e.g. `2020-01-01`.
+    #[arg(long, global = true)]
+    pub since: Option<NaiveDate>,
+
+    /// Only analyze issues created before this date.
+    #[arg(long, global = true)]
+    pub until: Option<NaiveDate>,
+
+    /// Skip issues with anomalies in their timeline, like those listed by the
+    /// `validate` subcommand.
+    #[arg(long, global = true)]
+    pub exclude_anomalous: bool,
+
+    /// Compute ages as of this date or RFC 3339 timestamp instead of now, to
+    /// get the same results as an earlier run. Some subcommands, like
+    /// `old-mcve`, also reconstruct the state of issues at this time.
+    #[arg(long, global = true, value_parser = parse_timestamp)]
+    pub as_of: Option<DateTime<FixedOffset>>,
+
+    #[arg(skip)]
+    pub loaded_config: Config,
+
+    #[arg(skip)]
+    pub started_at: Option<DateTime<FixedOffset>>,
+}
+
+#[derive(clap::ValueEnum, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ProgressMode {
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Repository {
+    pub owner: String,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Source {
+    GitHub,
+    Dump(PathBuf),
+}
+
+#[derive(clap::Subcommand, Debug)]
+pub enum Command {
+    McveEffectiveness(mcve_effectiveness::Args),
+    OldMcve(old_mcve::Args),
+    WithEvent(with_event::Args),
+    McveControlGroup(mcve_control_group::Args),
+    FirstResponse(first_response::Args),
+    Triage(triage::Args),
+    Backlog(backlog::Args),
+    StalePolicy(stale_policy::Args),
+    Validate(validate::Args),
+    /// A preset from the configuration file.
+    #[command(external_subcommand)]
+    Preset(Vec<OsString>),
+}
+
+pub async fn main() -> anyhow::Result<()> {
+    run(parse(std::env::args_os().collect())?).await
+}
+
+/// Runs `subcommand` with the arguments of the process, for the binaries that
+/// predate the subcommands.
+pub async fn alias_main(subcommand: &str) -> anyhow::Result<()> {
+    run(parse(alias_args(subcommand, std::env::args_os()))?).await
+}
+
+/// Parses `args` with the values from the configuration file added. Exits the
+/// process on invalid arguments, like [`clap::Parser::parse`].
+pub fn parse(args: Vec<OsString>) -> anyhow::Result<Cli> {
+    let config = Config::load(config_path(&args).as_deref())?;
+    let args = add_config_args(args, &config)?;
+    let mut cli = <Cli as clap::Parser>::parse_from(args);
+    cli.global.loaded_config = config;
+    // So that all ages are computed as of the same time.
+    cli.global.started_at = Some(Utc::now().fixed_offset());
+    Ok(cli)
+}
+
+/// Finds `--config` without parsing, since the configuration decides how to
+/// parse.
+fn config_path(args: &[OsString]) -> Option<PathBuf> {
+    let mut args = args.iter().skip(1).take_while(|arg| *arg != "--");
+    while let Some(arg) = args.next() {
+        if arg == "--config" {
+            return args.next().map(PathBuf::from);
+        }
+        if let Some(path) = arg.to_str().and_then(|arg| arg.strip_prefix("--config=")) {
+            return Some(PathBuf::from(path));
+        }
+    }
+    None
+}
+
+/// Replaces a preset name with its subcommand, and appends the options of the
+/// preset and the `[defaults]` of `config` that are not in `args`.
+fn add_config_args(mut args: Vec<OsString>, config: &Config) -> anyhow::Result<Vec<OsString>> {
+    let mut command = Cli::command();
+    command.build();
+    let get_matches = |args: &[OsString]| {
+        command
+            .clone()
+            .try_get_matches_from(args)
+            .unwrap_or_else(|e| e.exit())
+    };
+
+    let mut options = vec![];
+    let matches = get_matches(&args);
+    let (name, sub_matches) = matches.subcommand().expect("subcommand is required");
+    if command.find_subcommand(name).is_none() {
+        let preset = config
+            .presets
+            .get(name)
+            .with_context(|| format!("`{name}` is neither a subcommand nor a preset"))?;
+        anyhow::ensure!(
+            command.find_subcommand(&preset.command).is_some(),
+            "preset `{name}` has unknown command `{}`",
+            preset.command
+        );
+        // The preset name is followed by the rest of the arguments.
+        let rest = sub_matches
+            .get_many::<OsString>("")
+            .map_or(0, Iterator::count);
+        let position = args.len() - rest - 1;
+        args[position] = preset.command.clone().into();
+        options.extend(
+            preset
+                .options
+                .iter()
+                .map(|(key, value)| (format!("[presets.{name}]"), key, value)),
+        );
+    }
+    options.extend(
+        config
+            .defaults
+            .iter()
+            .map(|(key, value)| ("[defaults]".to_owned(), key, value)),
+    );
+
+    let matches = get_matches(&args);
+    let (name, sub_matches) = matches.subcommand().expect("subcommand is required");
+    let subcommand = command.find_subcommand(name).expect("checked above");
+    let mut added = vec![];
+    for (table, key, value) in options {
+        // `[defaults]` is for all subcommands, so only global options make sense.
+        let arg = subcommand
+            .get_arguments()
+            .filter(|arg| arg.is_global_set() || table != "[defaults]")
+            .find(|arg| arg.get_long() == Some(key.as_str()))
+            .with_context(|| format!("unknown option `{key}` in {table} of the configuration"))?;
+
+        let id = arg.get_id().as_str();
+        if sub_matches.value_source(id) == Some(ValueSource::CommandLine) || added.contains(&id) {
+            continue;
+        }
+        added.push(id);
+
+        let values: Vec<_> = match value {
+            toml::Value::Array(values) => values.iter().collect(),
+            value => vec![value],
+        };
+        for value in values {
+            let value = match value {
+                toml::Value::String(s) => s.clone(),
+                toml::Value::Integer(_) | toml::Value::Float(_) | toml::Value::Datetime(_) => {
+                    value.to_string()
+                }
+                toml::Value::Boolean(true) if !arg.get_action().takes_values() => {
+                    args.push(format!("--{key}").into());
+                    continue;
+                }
+                toml::Value::Boolean(false) if !arg.get_action().takes_values() => continue,
+                toml::Value::Boolean(b) => b.to_string(),
+                toml::Value::Array(_) | toml::Value::Table(_) => {
+                    anyhow::bail!("unsupported value for `{key}` in {table} of the configuration")
+                }
+            };
+            args.push(format!("--{key}={value}").into());
+        }
+    }
+
+    Ok(args)
+}
+
+fn alias_args(subcommand: &str, args: impl Iterator<Item = OsString>) -> Vec<OsString> {
+    let mut args: Vec<_> = args.collect();
+    args.insert(args.len().min(1), subcommand.into());
+    args
+}
+
+pub async fn run(cli: Cli) -> anyhow::Result<()> {
+    log_init()?;
+
+    cli.command.run(&cli.global).await
+}
+
+async fn plan(global: &GlobalArgs, command: &Command) -> anyhow::Result<()> {
+    if global.source != Source::GitHub {
+        anyhow::bail!("--plan needs --source github");
+    }
+    let query = command.issues_query(global)?;
+    let plan = global.github()?.plan(&query).await?;
+    Output::stdout(global.format).print(&plan)
+}
+
+impl Command {
+    /// Runs the subcommand, or with `--plan` only estimates what it needs.
+    /// Unlike [`run`], logging is not set up.
+    pub async fn run(self, global: &GlobalArgs) -> anyhow::Result<()> {
+        if global.plan {
+            return plan(global, &self).await;
+        }
+        match self {
+            Command::McveEffectiveness(args) => mcve_effectiveness::run(global, args).await,
+            Command::OldMcve(args) => old_mcve::run(global, args).await,
+            Command::WithEvent(args) => with_event::run(global, args).await,
+            Command::McveControlGroup(args) => mcve_control_group::run(global, args).await,
+            Command::FirstResponse(args) => first_response::run(global, args).await,
+            Command::Triage(args) => triage::run(global, args).await,
+            Command::Backlog(args) => backlog::run(global, args).await,
+            Command::StalePolicy(args) => stale_policy::run(global, args).await,
+            Command::Validate(args) => validate::run(global, args).await,
+            Command::Preset(args) => unreachable!("{args:?} is replaced by add_config_args"),
+        }
+    }
+
+    /// The issues the subcommand goes through.
+    fn issues_query(&self, global: &GlobalArgs) -> anyhow::Result<IssuesQuery> {
+        match self {
+            Command::McveEffectiveness(args) => mcve_effectiveness::issues_query(global, args),
+            Command::OldMcve(args) => old_mcve::issues_query(global, args),
+            Command::WithEvent(args) => with_event::issues_query(global, args),
+            Command::McveControlGroup(args) => mcve_control_group::issues_query(global, args),
+            Command::FirstResponse(args) => first_response::issues_query(global, args),
+            Command::Triage(args) => triage::issues_query(global, args),
+            Command::Backlog(args) => backlog::issues_query(global, args),
+            Command::StalePolicy(args) => stale_policy::issues_query(global, args),
+            Command::Validate(args) => validate::issues_query(global, args),
+            Command::Preset(args) => unreachable!("{args:?} is replaced by add_config_args"),
+        }
+    }
+}
+
+impl GlobalArgs {
+    pub fn repository(&self) -> anyhow::Result<Repository> {
+        self.loaded_config
+            .repository(&self.repo)
+            .parse()
+            .map_err(anyhow::Error::msg)
+    }
+
+    /// `--as-of`, or the time the arguments were parsed.
+    pub fn as_of(&self) -> DateTime<FixedOffset> {
+        self.as_of
+            .or(self.started_at)
+            .unwrap_or_else(|| Utc::now().fixed_offset())
+    }
+
+    /// Resolves a label alias from the configuration file.
+    pub fn label<'a>(&'a self, label: &'a str) -> &'a str {
+        self.loaded_config.label(label)
+    }
+
+    /// Adds the repository and page size to `variables`, and limits the query
+    /// to `--since` and `--until`.
+    pub fn issues_query(&self, mut variables: serde_json::Value) -> anyhow::Result<IssuesQuery> {
+        let repository = self.repository()?;
+        variables["owner"] = repository.owner.into();
+        variables["name"] = repository.name.into();
+        variables["page_size"] = self.page_size.into();
+
+        let mut query = IssuesQuery::new(variables, self.pages);
+        query.created_since = self.since.map(start_of_day);
+        query.created_until = self.until.map(start_of_day);
+        query.exclude_anomalous = self.exclude_anomalous;
+        query.states_at = Some(self.as_of());
+        Ok(query)
+    }
+
+    /// A GitHub client configured by the options. `--cache` is not used.
+    pub fn github(&self) -> anyhow::Result<GitHub> {
+        let mut builder = GitHub::builder();
+        if let Some(token) = &self.token {
+            builder = builder.token(token.clone());
+        }
+        if let Some(path) = &self.token_file {
+            builder = builder.token_file(path.clone());
+        }
+        if let Some(api_url) = &self.api_url {
+            builder = builder.base_url(api_url.clone());
+        }
+        if let Some(user_agent) = &self.user_agent {
+            builder = builder.user_agent(user_agent.clone());
+        }
+        if let Some(timeout) = self.timeout {
+            builder = builder.timeout(Duration::from_secs(timeout));
+        }
+        if let Some(max_requests) = self.max_requests {
+            builder = builder.max_requests(max_requests);
+        }
+        if let Some(max_cost) = self.max_cost {
+            builder = builder.max_cost(max_cost);
+        }
+        builder.build()
+    }
+
+    pub fn issue_source(&self) -> anyhow::Result<IssueSource> {
+        Ok(match &self.source {
+            Source::GitHub => {
+                let mut github = self.github()?;
+                if let Some(cache_dir) = &self.cache {
+                    github = github.with_cache_dir(cache_dir.clone());
+                }
+                let show_progress = match self.progress {
+                    ProgressMode::Auto => ProgressDisplay::is_supported(),
+                    ProgressMode::Always => true,
+                    ProgressMode::Never => false,
+                };
+                if show_progress {
+                    github = github.with_progress(ProgressDisplay);
+                }
+                IssueSource::GitHub(Box::new(github))
+            }
+            Source::Dump(path) => IssueSource::Dump(path.clone()),
+        })
+    }
+}
+
+fn start_of_day(date: NaiveDate) -> DateTime<Utc> {
+    date.and_hms_opt(0, 0, 0)
+        .expect("midnight exists")
+        .and_utc()
+}
+
+impl FromStr for Repository {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once('/') {
+            Some((owner, name)) if !owner.is_empty() && !name.is_empty() && !name.contains('/') => {
+                Ok(Self {
+                    owner: owner.to_owned(),
+                    name: name.to_owned(),
+                })
+            }
+            _ => Err(format!("expected `owner/name`, got `{s}`")),
+        }
+    }
+}
+
+impl Display for Repository {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}", self.owner, self.name)
+    }
+}
+
+impl FromStr for Source {
+    type Err = std::convert::Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(if s == "github" {
+            Source::GitHub
+        } else {
+            Source::Dump(PathBuf::from(s))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use super::*;
+
+    #[test]
+    fn test_alias_args() {
+        let args = alias_args(
+            "old-mcve",
+            [
+                "list-old-e-needs-mcve-issues",
+                "--pages",
+                "3",
+                "--format",
+                "json",
+            ]
+            .into_iter()
+            .map(OsString::from),
+        );
+        let cli = Cli::try_parse_from(args).unwrap();
+
+        assert_eq!(cli.global.pages, 3);
+        assert_eq!(cli.global.format, OutputFormat::Json);
+        assert!(matches!(cli.command, Command::OldMcve(_)));
+    }
+
+    #[test]
+    fn test_global_args() {
+        let cli = Cli::try_parse_from([
+            "rust-issue-stats",
+            "--repo",
+            "rust-lang/cargo",
+            "backlog",
+            "--since",
+            "2020-01-01",
+            "--source",
+            "issues.jsonl",
+        ])
+        .unwrap();
+
+        let query = cli
+            .global
+            .issues_query(serde_json::json!({
+                "timelineItemTypes": ["CLOSED_EVENT"],
+            }))
+            .unwrap();
+        assert_eq!(
+            query.variables,
+            serde_json::json!({
+                "timelineItemTypes": ["CLOSED_EVENT"],
+                "owner": "rust-lang",
+                "name": "cargo",
+                "page_size": 10,
+            })
+        );
+        assert_eq!(
+            query.created_since,
+            Some(DateTime::from_str("2020-01-01T00:00:00Z").unwrap())
+        );
+        assert_eq!(cli.global.source, Source::Dump("issues.jsonl".into()));
+
+        assert!("rust-lang".parse::<Repository>().is_err());
+    }
+
+    fn os_strings(args: &[&str]) -> Vec<OsString> {
+        args.iter().map(OsString::from).collect()
+    }
+
+    #[test]
+    fn test_config_args() {
+        let config: Config = toml::from_str(
+            r#"
+            [defaults]
+            repo = "cargo"
+            page-size = 50
+
+            [repositories]
+            cargo = "rust-lang/cargo"
+
+            [presets.stale-mcve]
+            command = "old-mcve"
+            label-months-considered-old = 24
+            last-comment-months-considered-old = 12
+            page-size = 20
+            "#,
+        )
+        .unwrap();
+
+        let args = add_config_args(
+            os_strings(&[
+                "rust-issue-stats",
+                "--pages=5",
+                "stale-mcve",
+                "--last-comment-months-considered-old",
+                "6",
+            ]),
+            &config,
+        )
+        .unwrap();
+        assert_eq!(
+            args,
+            os_strings(&[
+                "rust-issue-stats",
+                "--pages=5",
+                "old-mcve",
+                "--last-comment-months-considered-old",
+                "6",
+                "--label-months-considered-old=24",
+                "--page-size=20",
+                "--repo=cargo",
+            ])
+        );
+
+        let mut cli = Cli::try_parse_from(args).unwrap();
+        cli.global.loaded_config = config.clone();
+        assert_eq!(
+            cli.global.repository().unwrap().to_string(),
+            "rust-lang/cargo"
+        );
+
+        let args = add_config_args(
+            os_strings(&["rust-issue-stats", "--page-size", "7", "stale-mcve"]),
+            &config,
+        )
+        .unwrap();
+        assert!(!args.contains(&"--page-size=20".into()));
+
+        assert!(add_config_args(os_strings(&["rust-issue-stats", "stale"]), &config).is_err());
+
+        let mut unknown_option = config.clone();
+        unknown_option
+            .defaults
+            .insert("bucket".to_string(), "day".into());
+        assert!(add_config_args(
+            os_strings(&["rust-issue-stats", "backlog"]),
+            &unknown_option
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_config_path() {
+        assert_eq!(
+            config_path(&os_strings(&["x", "backlog", "--config", "a.toml"])),
+            Some(PathBuf::from("a.toml"))
+        );
+        assert_eq!(
+            config_path(&os_strings(&["x", "--config=b.toml", "backlog"])),
+            Some(PathBuf::from("b.toml"))
+        );
+        assert_eq!(config_path(&os_strings(&["x", "backlog"])), None);
+    }
+}
@@ -0,0 +1,352 @@
+//! Configuration of the [`GitHub`] client: where the token comes from, which
+//! API endpoint is used, and how long requests may take.
+
+use anyhow::Context;
+use http::header::{HeaderValue, AUTHORIZATION, USER_AGENT};
+use octocrab::service::middleware::{base_uri::BaseUriLayer, extra_headers::ExtraHeadersLayer};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::GitHub;
+
+const DEFAULT_BASE_URL: &str = "https://api.github.com";
+
+const DEFAULT_USER_AGENT: &str = "octocrab";
+
+const TOKEN_HELP: &str = "pass --token or --token-file, set GITHUB_TOKEN, or log in with `gh auth login`. \
+    Tokens can be generated at https://github.com/settings/tokens?type=beta and only need to read public repos";
+
+/// Builds a [`GitHub`]. The token is the first of
+///
+/// 1. [`Self::token`],
+/// 2. the contents of [`Self::token_file`],
+/// 3. the `GITHUB_TOKEN` or `GH_TOKEN` environment variable,
+/// 4. the token of the API host in the `hosts.yml` of the gh CLI.
+#[derive(Debug, Default, Clone)]
+pub struct GitHubBuilder {
+    token: Option<String>,
+    token_file: Option<PathBuf>,
+    base_url: Option<String>,
+    user_agent: Option<String>,
+    timeout: Option<Duration>,
+    max_requests: Option<u64>,
+    max_cost: Option<u64>,
+}
+
+/// A request took longer than [`GitHubBuilder::timeout`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RequestTimeout(pub Duration);
+
+/// The limits of [`GitHubBuilder::max_requests`] and
+/// [`GitHubBuilder::max_cost`], and what has been used so far. Cached responses
+/// are free.
+#[derive(Debug, Default)]
+pub(crate) struct Budget {
+    max_requests: Option<u64>,
+    max_cost: Option<u64>,
+    requests: AtomicU64,
+    cost: AtomicU64,
+    last_cost: AtomicU64,
+}
+
+/// Uncached API requests made by a [`GitHub`], and the rate limit points they
+/// cost.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ApiUsage {
+    pub requests: u64,
+    pub cost: u64,
+}
+
+/// A request was not made because it would exceed the budget.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BudgetExhausted(pub ApiUsage);
+
+impl GitHubBuilder {
+    pub fn token(mut self, token: String) -> Self {
+        self.token = Some(token);
+        self
+    }
+
+    /// A file that contains the token, e.g. one written by a secret manager.
+    pub fn token_file(mut self, path: PathBuf) -> Self {
+        self.token_file = Some(path);
+        self
+    }
+
+    /// Use another endpoint than `https://api.github.com`, e.g.
+    /// `https://github.example.com/api` for GitHub Enterprise or a mock server.
+    /// GraphQL requests go to `{base_url}/graphql`. Without this,
+    /// `GITHUB_API_URL` is used if set.
+    pub fn base_url(mut self, base_url: String) -> Self {
+        self.base_url = Some(base_url);
+        self
+    }
+
+    /// Sent with every request instead of the `octocrab` user agent.
+    pub fn user_agent(mut self, user_agent: String) -> Self {
+        self.user_agent = Some(user_agent);
+        self
+    }
+
+    /// The maximum time for one request. Longer requests fail with
+    /// [`RequestTimeout`].
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// The maximum number of API requests. Further requests fail with
+    /// [`BudgetExhausted`].
+    pub fn max_requests(mut self, max_requests: u64) -> Self {
+        self.max_requests = Some(max_requests);
+        self
+    }
+
+    /// The maximum number of GraphQL rate limit points to spend. A request is
+    /// not made if it would exceed this if it cost as much as the previous one.
+    pub fn max_cost(mut self, max_cost: u64) -> Self {
+        self.max_cost = Some(max_cost);
+        self
+    }
+
+    pub fn build(self) -> anyhow::Result<GitHub> {
+        let base_url = self
+            .base_url
+            .clone()
+            .or_else(|| std::env::var("GITHUB_API_URL").ok());
+        let token = self.resolve_token(base_url.as_deref())?;
+        let base_url = base_url.as_deref().unwrap_or(DEFAULT_BASE_URL);
+        let base_uri: http::Uri = base_url
+            .parse()
+            .map_err(|e| anyhow::anyhow!("invalid GitHub API URL {base_url:?}: {e}"))?;
+
+        // `Octocrab::builder()` always sends its own user agent, and another
+        // one would be sent in addition to it, so this is the same client
+        // with only one.
+        let user_agent = self.user_agent.as_deref().unwrap_or(DEFAULT_USER_AGENT);
+        let headers = vec![
+            (
+                USER_AGENT,
+                HeaderValue::from_str(user_agent)
+                    .with_context(|| format!("invalid user agent {user_agent:?}"))?,
+            ),
+            (
+                AUTHORIZATION,
+                HeaderValue::from_str(&format!("Bearer {token}"))
+                    .context("invalid GitHub token")?,
+            ),
+        ];
+        let connector = hyper_rustls::HttpsConnectorBuilder::new()
+            .with_native_roots()
+            .https_or_http()
+            .enable_http1()
+            .build();
+        let octocrab = octocrab::OctocrabBuilder::new_empty()
+            .with_service(hyper::Client::builder().build::<_, String>(connector))
+            .with_layer(&ExtraHeadersLayer::new(Arc::new(headers)))
+            .with_layer(&BaseUriLayer::new(base_uri))
+            .with_auth(octocrab::AuthState::None)
+            .build()?;
+
+        Ok(GitHub {
+            timeout: self.timeout,
+            budget: Budget {
+                max_requests: self.max_requests,
+                max_cost: self.max_cost,
+                ..Budget::default()
+            },
+            ..GitHub::from_octocrab(octocrab)
+        })
+    }
+
+    fn resolve_token(&self, base_url: Option<&str>) -> anyhow::Result<String> {
+        if let Some(token) = &self.token {
+            return non_empty(token.clone(), "--token");
+        }
+        if let Some(path) = &self.token_file {
+            let token = std::fs::read_to_string(path)
+                .with_context(|| format!("failed to read token file {}", path.display()))?;
+            return non_empty(token, &path.display().to_string());
+        }
+        for var in ["GITHUB_TOKEN", "GH_TOKEN"] {
+            if let Ok(token) = std::env::var(var) {
+                return non_empty(token, var);
+            }
+        }
+
+        let host = api_host(base_url);
+        if let Some(path) = gh_hosts_path() {
+            if let Ok(hosts) = std::fs::read_to_string(&path) {
+                if let Some(token) = token_from_gh_hosts(&hosts, &host) {
+                    return Ok(token);
+                }
+            }
+        }
+        anyhow::bail!("no GitHub token for {host}: {TOKEN_HELP}")
+    }
+}
+
+fn non_empty(token: String, source: &str) -> anyhow::Result<String> {
+    let token = token.trim();
+    if token.is_empty() {
+        anyhow::bail!("the GitHub token from {source} is empty: {TOKEN_HELP}");
+    }
+    Ok(token.to_owned())
+}
+
+/// The host that gh stores the token under, e.g. `github.example.com` for
+/// `https://github.example.com/api`.
+fn api_host(base_url: Option<&str>) -> String {
+    let Some(base_url) = base_url else {
+        return "github.com".to_owned();
+    };
+    let host = base_url
+        .split_once("://")
+        .map_or(base_url, |(_, rest)| rest)
+        .split(['/', ':'])
+        .next()
+        .unwrap_or_default();
+    match host {
+        "api.github.com" => "github.com".to_owned(),
+        host => host.to_owned(),
+    }
+}
+
+fn gh_hosts_path() -> Option<PathBuf> {
+    if let Ok(dir) = std::env::var("GH_CONFIG_DIR") {
+        return Some(Path::new(&dir).join("hosts.yml"));
+    }
+    let config_dir = match std::env::var("XDG_CONFIG_HOME") {
+        Ok(dir) => PathBuf::from(dir),
+        Err(_) => Path::new(&std::env::var("HOME").ok()?).join(".config"),
+    };
+    Some(config_dir.join("gh/hosts.yml"))
+}
+
+/// The `oauth_token` of `host` in a gh `hosts.yml`, which looks like
+///
+/// ```yaml
+/// github.com:
+///     user: octocat
+///     oauth_token: gho_...
+///     git_protocol: https
+/// ```
+///
+/// Newer versions of gh keep the token in the system keyring instead, which is
+/// not supported.
+fn token_from_gh_hosts(hosts: &str, host: &str) -> Option<String> {
+    let mut in_host = false;
+    // The indentation of the direct children of the host, so that tokens in
+    // `users:` are ignored.
+    let mut child_indent = None;
+    for line in hosts.lines() {
+        if line.trim().is_empty() || line.trim_start().starts_with('#') {
+            continue;
+        }
+        let indent = line.len() - line.trim_start().len();
+        if indent == 0 {
+            in_host = line.trim_end().strip_suffix(':') == Some(host);
+            child_indent = None;
+            continue;
+        }
+        if in_host && *child_indent.get_or_insert(indent) == indent {
+            if let Some(token) = line.trim().strip_prefix("oauth_token:") {
+                let token = token.trim().trim_matches(['"', '\'']);
+                if !token.is_empty() {
+                    return Some(token.to_owned());
+                }
+            }
+        }
+    }
+    None
+}
+
+impl Budget {
+    /// Fails if another request would exceed the budget.
+    pub(crate) fn check(&self) -> Result<(), BudgetExhausted> {
+        let used = self.used();
+        let next_cost = self.last_cost.load(Ordering::Relaxed).max(1);
+        if self.max_requests.is_some_and(|max| used.requests >= max)
+            || self.max_cost.is_some_and(|max| used.cost + next_cost > max)
+        {
+            return Err(BudgetExhausted(used));
+        }
+        Ok(())
+    }
+
+    /// Records a request that cost `cost` points.
+    pub(crate) fn spend(&self, cost: u64) {
+        self.requests.fetch_add(1, Ordering::Relaxed);
+        self.cost.fetch_add(cost, Ordering::Relaxed);
+        self.last_cost.store(cost, Ordering::Relaxed);
+    }
+
+    pub(crate) fn used(&self) -> ApiUsage {
+        ApiUsage {
+            requests: self.requests.load(Ordering::Relaxed),
+            cost: self.cost.load(Ordering::Relaxed),
+        }
+    }
+}
+
+impl std::fmt::Display for BudgetExhausted {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "the API budget is exhausted after {} requests costing {} points",
+            self.0.requests, self.0.cost
+        )
+    }
+}
+
+impl std::error::Error for BudgetExhausted {}
+
+impl std::fmt::Display for RequestTimeout {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "the GitHub API request timed out after {:?}", self.0)
+    }
+}
+
+impl std::error::Error for RequestTimeout {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_from_gh_hosts() {
+        let hosts = "\
+github.com:
+    users:
+        octocat:
+            oauth_token: gho_not_this_one
+    user: octocat
+    oauth_token: gho_github
+    git_protocol: https
+github.example.com:
+    oauth_token: \"gho_enterprise\"
+";
+        assert_eq!(
+            token_from_gh_hosts(hosts, "github.com").as_deref(),
+            Some("gho_github")
+        );
+        assert_eq!(
+            token_from_gh_hosts(hosts, "github.example.com").as_deref(),
+            Some("gho_enterprise")
+        );
+        assert_eq!(token_from_gh_hosts(hosts, "example.com"), None);
+    }
+
+    #[test]
+    fn test_api_host() {
+        assert_eq!(api_host(None), "github.com");
+        assert_eq!(api_host(Some("https://api.github.com")), "github.com");
+        assert_eq!(
+            api_host(Some("https://github.example.com/api")),
+            "github.example.com"
+        );
+        assert_eq!(api_host(Some("http://127.0.0.1:8080")), "127.0.0.1");
+    }
+}
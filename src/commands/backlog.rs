@@ -0,0 +1,289 @@
+use chrono::{Datelike, Duration, NaiveDate};
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+
+use crate::cli::GlobalArgs;
+use crate::*;
+
+/// Reconstructs the number of open issues over time, optionally restricted to
+/// issues with a given label or a label in a given category.
+#[derive(clap::Args, Debug)]
+pub struct Args {
+    #[arg(long, value_enum, default_value = "week")]
+    bucket: Bucket,
+
+    /// Only count issues while they have this label.
+    #[arg(long, conflicts_with = "label_category")]
+    label: Option<String>,
+
+    /// Only count issues while they have a label in this category, e.g. `T`.
+    #[arg(long)]
+    label_category: Option<LabelCategory>,
+
+    /// Also write a self-contained HTML report with charts to this file.
+    #[arg(long)]
+    report: Option<PathBuf>,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+enum Bucket {
+    Day,
+    Week,
+}
+
+/// How the backlog changed during a bucket. `inflow` counts issues that were
+/// created, reopened or got a matching label, and `outflow` issues that were
+/// closed or lost their last matching label.
+#[derive(Debug, PartialEq, serde::Serialize)]
+pub struct BacklogRow {
+    date: NaiveDate,
+    inflow: u64,
+    outflow: u64,
+    net: i64,
+    open: i64,
+}
+
+pub async fn run(global: &GlobalArgs, mut args: Args) -> anyhow::Result<()> {
+    args.label = args.label.map(|label| global.label(&label).to_owned());
+
+    let source = global.issue_source()?;
+
+    let flows = RefCell::new(BTreeMap::<NaiveDate, (u64, u64)>::new());
+    let analyzed_issues = RefCell::new(0);
+
+    let as_of = global.as_of();
+    source
+        .for_issues_with_timeline(
+            &issues_query(global, &args)?,
+            |issue| {
+                *analyzed_issues.borrow_mut() += 1;
+                add_flows(
+                    &mut flows.borrow_mut(),
+                    &backlog_intervals(issue, &args),
+                    args.bucket,
+                    as_of,
+                );
+            },
+            || eprintln!("Analyzed {} issues", analyzed_issues.borrow()),
+        )
+        .await?;
+
+    let rows = rows(&flows.into_inner(), args.bucket, as_of.date_naive());
+    let mut output = Output::stdout(global.format);
+    for row in &rows {
+        output.print(row)?;
+    }
+
+    if let Some(report_path) = &args.report {
+        write_report(report_path, &rows)?;
+    }
+
+    Ok(())
+}
+
+/// The issues that `run` goes through.
+pub fn issues_query(global: &GlobalArgs, args: &Args) -> anyhow::Result<IssuesQuery> {
+    let mut timeline_item_types = vec!["CLOSED_EVENT", "REOPENED_EVENT"];
+    if args.label.is_some() || args.label_category.is_some() {
+        timeline_item_types.extend(["LABELED_EVENT", "UNLABELED_EVENT"]);
+    }
+    global.issues_query(serde_json::json!({
+        "timelineItemTypes": timeline_item_types,
+    }))
+}
+
+fn write_report(path: &Path, rows: &[BacklogRow]) -> anyhow::Result<()> {
+    let mut report = HtmlReport::new("Open issue backlog");
+    let series = |value: fn(&BacklogRow) -> f64| -> Vec<(String, f64)> {
+        rows.iter()
+            .map(|row| (row.date.to_string(), value(row)))
+            .collect()
+    };
+    report.add_bar_chart("Open issues", &series(|row| row.open as f64));
+    report.add_bar_chart("Inflow", &series(|row| row.inflow as f64));
+    report.add_bar_chart("Outflow", &series(|row| row.outflow as f64));
+
+    eprintln!("Writing report to {path:?}");
+    report.write_to(path)?;
+    Ok(())
+}
+
+/// The periods during which the issue counts towards the backlog.
+fn backlog_intervals(issue: &IssueWithTimelineItems, args: &Args) -> Vec<Interval> {
+    let open = issue.open_intervals();
+
+    let labeled = if let Some(label) = &args.label {
+        issue.label_intervals(label)
+    } else if let Some(category) = args.label_category {
+        union(
+            issue
+                .label_history()
+                .intervals
+                .into_iter()
+                .filter(|(name, _)| LabelCategory::of(name) == category)
+                .flat_map(|(_, intervals)| intervals)
+                .collect(),
+        )
+    } else {
+        return open;
+    };
+
+    intersection(&open, &labeled)
+}
+
+/// Counts the start of each interval as inflow and its end as outflow of their
+/// buckets. Starts and ends after `as_of` have not happened yet.
+fn add_flows(
+    flows: &mut BTreeMap<NaiveDate, (u64, u64)>,
+    intervals: &[Interval],
+    bucket: Bucket,
+    as_of: DateTime<FixedOffset>,
+) {
+    for interval in intervals.iter().filter(|i| i.start <= as_of) {
+        flows
+            .entry(bucket.start_of(interval.start.date_naive()))
+            .or_default()
+            .0 += 1;
+        if let Some(end) = interval.end.filter(|end| *end <= as_of) {
+            flows
+                .entry(bucket.start_of(end.date_naive()))
+                .or_default()
+                .1 += 1;
+        }
+    }
+}
+
+/// One row per bucket from the first bucket with any change up to and including
+/// the bucket of `until`. Later changes are left out.
+fn rows(
+    flows: &BTreeMap<NaiveDate, (u64, u64)>,
+    bucket: Bucket,
+    until: NaiveDate,
+) -> Vec<BacklogRow> {
+    let Some(first) = flows.keys().next() else {
+        return vec![];
+    };
+    let last = bucket.start_of(until);
+
+    let mut rows = vec![];
+    let mut open = 0;
+    let mut date = *first;
+    while date <= last {
+        let (inflow, outflow) = flows.get(&date).copied().unwrap_or_default();
+        let net = inflow as i64 - outflow as i64;
+        open += net;
+        rows.push(BacklogRow {
+            date,
+            inflow,
+            outflow,
+            net,
+            open,
+        });
+        date += bucket.length();
+    }
+    rows
+}
+
+impl Display for BacklogRow {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} +{} -{} net {} open {}",
+            self.date, self.inflow, self.outflow, self.net, self.open
+        )
+    }
+}
+
+impl Bucket {
+    fn start_of(&self, date: NaiveDate) -> NaiveDate {
+        match self {
+            Bucket::Day => date,
+            Bucket::Week => date - Duration::days(date.weekday().num_days_from_monday() as i64),
+        }
+    }
+
+    fn length(&self) -> Duration {
+        match self {
+            Bucket::Day => Duration::days(1),
+            Bucket::Week => Duration::weeks(1),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn test_rows() {
+        let mut flows = BTreeMap::new();
+        flows.insert(date("2020-01-06"), (3, 0));
+        flows.insert(date("2020-01-20"), (1, 2));
+
+        let row = |date_str, inflow, outflow, net, open| BacklogRow {
+            date: date(date_str),
+            inflow,
+            outflow,
+            net,
+            open,
+        };
+        assert_eq!(
+            rows(&flows, Bucket::Week, date("2020-01-29")),
+            vec![
+                row("2020-01-06", 3, 0, 3, 3),
+                row("2020-01-13", 0, 0, 0, 3),
+                row("2020-01-20", 1, 2, -1, 2),
+                row("2020-01-27", 0, 0, 0, 2),
+            ]
+        );
+
+        // A close after `until` is left out.
+        flows.insert(date("2020-02-10"), (0, 1));
+        assert_eq!(
+            rows(&flows, Bucket::Week, date("2020-01-22")),
+            vec![
+                row("2020-01-06", 3, 0, 3, 3),
+                row("2020-01-13", 0, 0, 0, 3),
+                row("2020-01-20", 1, 2, -1, 2),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_add_flows_as_of() {
+        let at = |s: &str| DateTime::parse_from_rfc3339(s).unwrap();
+        let intervals = [
+            Interval {
+                start: at("2020-01-06T00:00:00Z"),
+                end: Some(at("2020-02-10T00:00:00Z")),
+            },
+            Interval {
+                start: at("2020-03-02T00:00:00Z"),
+                end: None,
+            },
+        ];
+        let mut flows = BTreeMap::new();
+        add_flows(
+            &mut flows,
+            &intervals,
+            Bucket::Week,
+            at("2020-02-01T00:00:00Z"),
+        );
+
+        // Closed after `as_of`, so still open in every row.
+        assert_eq!(flows, BTreeMap::from([(date("2020-01-06"), (1, 0))]));
+        assert_eq!(
+            rows(&flows, Bucket::Week, date("2020-02-01"))
+                .iter()
+                .map(|row| row.open)
+                .collect::<Vec<_>>(),
+            vec![1, 1, 1, 1]
+        );
+    }
+}
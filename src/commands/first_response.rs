@@ -0,0 +1,407 @@
+use std::cell::RefCell;
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+
+use crate::cli::GlobalArgs;
+use crate::*;
+
+/// Reports how long new issues wait for the first comment from someone other
+/// than the author of the issue.
+#[derive(clap::Args, Debug)]
+pub struct Args {
+    /// Open issues without a response that are older than this are listed.
+    #[arg(long, default_value = "7")]
+    waiting_days_considered_old: i64,
+
+    /// Also write a self-contained HTML report with charts to this file.
+    #[arg(long)]
+    report: Option<PathBuf>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Responder {
+    Member,
+    Bot,
+    Other,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum FirstResponse {
+    /// The first response came after the given number of days. The first
+    /// response from a human is tracked separately since bots often respond
+    /// immediately.
+    Responded {
+        days: f64,
+        by: Responder,
+        first_human_response_days: Option<f64>,
+    },
+
+    /// The issue was closed before anyone responded.
+    ClosedWithoutResponse,
+
+    /// Nobody has responded yet.
+    Waiting,
+}
+
+#[derive(Default, serde::Serialize)]
+struct Stats {
+    analyzed_issues: usize,
+    closed_without_response: usize,
+    #[serde(serialize_with = "serialize_as_distribution")]
+    response_days: Vec<f64>,
+    #[serde(serialize_with = "serialize_as_distribution")]
+    member_response_days: Vec<f64>,
+    #[serde(serialize_with = "serialize_as_distribution")]
+    bot_response_days: Vec<f64>,
+    #[serde(serialize_with = "serialize_as_distribution")]
+    other_response_days: Vec<f64>,
+    #[serde(serialize_with = "serialize_as_distribution")]
+    human_response_days: Vec<f64>,
+    waiting: Vec<WaitingIssue>,
+}
+
+#[derive(serde::Serialize)]
+struct WaitingIssue {
+    number: u32,
+    url: String,
+    title: String,
+    days: i64,
+}
+
+pub async fn run(global: &GlobalArgs, args: Args) -> anyhow::Result<()> {
+    let source = global.issue_source()?;
+
+    let stats = RefCell::new(Stats::default());
+
+    let as_of = global.as_of();
+    source
+        .for_issues_with_timeline(
+            &issues_query(global, &args)?,
+            |issue| {
+                let mut stats = stats.borrow_mut();
+                stats.analyzed_issues += 1;
+                let response = first_response(issue, as_of);
+                match response {
+                    FirstResponse::Responded {
+                        days,
+                        by,
+                        first_human_response_days,
+                    } => {
+                        stats.response_days.push(days);
+                        match by {
+                            Responder::Member => stats.member_response_days.push(days),
+                            Responder::Bot => stats.bot_response_days.push(days),
+                            Responder::Other => stats.other_response_days.push(days),
+                        }
+                        if let Some(days) = first_human_response_days {
+                            stats.human_response_days.push(days);
+                        }
+                    }
+                    FirstResponse::ClosedWithoutResponse => stats.closed_without_response += 1,
+                    FirstResponse::Waiting => {}
+                }
+
+                let days = as_of.signed_duration_since(issue.created_at).num_days();
+                if is_waiting_for_human(issue, &response, as_of)
+                    && days > args.waiting_days_considered_old
+                {
+                    stats.waiting.push(WaitingIssue {
+                        number: issue.number,
+                        url: issue.url.clone(),
+                        title: issue.title.clone(),
+                        days,
+                    });
+                }
+            },
+            || eprintln!("Analyzed {} issues", stats.borrow().analyzed_issues),
+        )
+        .await?;
+
+    let stats = stats.into_inner();
+    Output::stdout(global.format).print(&stats)?;
+
+    if let Some(report_path) = &args.report {
+        write_report(report_path, &stats)?;
+    }
+
+    Ok(())
+}
+
+/// The issues that `run` goes through.
+pub fn issues_query(global: &GlobalArgs, _args: &Args) -> anyhow::Result<IssuesQuery> {
+    global.issues_query(serde_json::json!({
+        "timelineItemTypes": ["ISSUE_COMMENT", "CLOSED_EVENT", "REOPENED_EVENT"],
+    }))
+}
+
+fn write_report(path: &Path, stats: &Stats) -> anyhow::Result<()> {
+    let mut report = HtmlReport::new("Time to first response");
+    report.add_paragraph(&format!(
+        "{} issues analyzed, {} closed without response.",
+        stats.analyzed_issues, stats.closed_without_response
+    ));
+    report.add_bar_chart(
+        "Time until first human response",
+        &day_histogram(&stats.human_response_days),
+    );
+    report.add_bar_chart(
+        "Time until first response by a member",
+        &day_histogram(&stats.member_response_days),
+    );
+
+    let rows: Vec<_> = stats
+        .waiting
+        .iter()
+        .map(|waiting| {
+            vec![
+                Cell::Link {
+                    text: format!("#{}", waiting.number),
+                    url: waiting.url.clone(),
+                    sort: Some(waiting.number),
+                },
+                Cell::Text(waiting.title.clone()),
+                Cell::Number(waiting.days as f64),
+            ]
+        })
+        .collect();
+    report.add_table(
+        "Open issues still waiting for a response",
+        &["Issue", "Title", "Days waiting"],
+        &rows,
+    );
+
+    eprintln!("Writing report to {path:?}");
+    report.write_to(path)?;
+    Ok(())
+}
+
+/// The first response to the issue as it was at `as_of`. Later comments and
+/// closes are ignored.
+pub fn first_response(
+    issue: &IssueWithTimelineItems,
+    as_of: DateTime<FixedOffset>,
+) -> FirstResponse {
+    let mut first_response = None;
+    let mut first_human_response_days = None;
+
+    for timeline_item in issue.chronological_timeline_items() {
+        if timeline_item.created_at() > as_of {
+            break;
+        }
+        match timeline_item {
+            TimelineItem::IssueComment {
+                created_at,
+                author,
+                author_association,
+            } => {
+                let login = author.as_ref().map(|author| &author.login);
+                if login.is_some() && login == issue.author.as_ref().map(|author| &author.login) {
+                    continue;
+                }
+
+                let responder = match (author, author_association) {
+                    (Some(author), _) if author.is_bot() => Responder::Bot,
+                    (_, Some(association)) if association.is_member() => Responder::Member,
+                    _ => Responder::Other,
+                };
+
+                let days = days_since(issue, created_at);
+                first_response.get_or_insert((days, responder));
+                if responder != Responder::Bot {
+                    first_human_response_days = Some(days);
+                    break;
+                }
+            }
+            TimelineItem::ClosedEvent { .. } if first_response.is_none() => {
+                return FirstResponse::ClosedWithoutResponse;
+            }
+            _ => {}
+        }
+    }
+
+    match first_response {
+        Some((days, by)) => FirstResponse::Responded {
+            days,
+            by,
+            first_human_response_days,
+        },
+        None => FirstResponse::Waiting,
+    }
+}
+
+/// Whether the issue is open and no human other than the author has responded
+/// yet. Issues that only got a comment from a bot, or that were closed without
+/// a response and reopened, are still waiting.
+pub fn is_waiting_for_human(
+    issue: &IssueWithTimelineItems,
+    response: &FirstResponse,
+    as_of: DateTime<FixedOffset>,
+) -> bool {
+    let human_responded = match response {
+        FirstResponse::Responded {
+            first_human_response_days,
+            ..
+        } => first_human_response_days.is_some(),
+        FirstResponse::ClosedWithoutResponse | FirstResponse::Waiting => false,
+    };
+    !human_responded && issue.was_open_at(as_of)
+}
+
+impl Display for Stats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Number of issues analyzed: {}", self.analyzed_issues)?;
+        writeln!(
+            f,
+            "Closed without response: {}",
+            self.closed_without_response
+        )?;
+        writeln!(
+            f,
+            "Days until first response by someone other than the author"
+        )?;
+        write_distribution(f, "    by anyone ", &self.response_days)?;
+        write_distribution(f, "    by a member", &self.member_response_days)?;
+        write_distribution(f, "    by a bot   ", &self.bot_response_days)?;
+        write_distribution(f, "    by others  ", &self.other_response_days)?;
+        write_distribution(
+            f,
+            "Days until first human response",
+            &self.human_response_days,
+        )?;
+        writeln!(f, "Open issues still waiting for a response:")?;
+        for waiting in &self.waiting {
+            writeln!(f, "    {waiting}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for WaitingIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} waiting {} days: {}", self.url, self.days, self.title)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    fn comment(created_at: &str, login: &str, association: AuthorAssociation) -> TimelineItem {
+        TimelineItem::IssueComment {
+            created_at: DateTime::from_str(created_at).unwrap(),
+            author: Some(Actor {
+                login: login.to_string(),
+                typename: Some("User".to_string()),
+            }),
+            author_association: Some(association),
+        }
+    }
+
+    #[test]
+    fn test_ignores_author_and_tracks_first_human() {
+        let issue = IssueWithTimelineItems {
+            url: "N/A".to_string(),
+            number: 42,
+            title: "Bot responds first".to_string(),
+            author: Some(Actor {
+                login: "reporter".to_string(),
+                typename: Some("User".to_string()),
+            }),
+            labels: Labels::new(vec![]),
+            created_at: DateTime::from_str("2020-01-01T00:00:00Z").unwrap(),
+            timeline_items: vec![
+                comment("2020-01-01T12:00:00Z", "reporter", AuthorAssociation::None),
+                comment(
+                    "2020-01-02T00:00:00Z",
+                    "rustbot",
+                    AuthorAssociation::Collaborator,
+                ),
+                comment("2020-01-04T00:00:00Z", "triager", AuthorAssociation::Member),
+            ],
+        };
+
+        let date = |date: &str| DateTime::from_str(date).unwrap();
+        assert_eq!(
+            first_response(&issue, date("2020-02-01T00:00:00Z")),
+            FirstResponse::Responded {
+                days: 1.0,
+                by: Responder::Bot,
+                first_human_response_days: Some(3.0),
+            }
+        );
+
+        // Responses after `as_of` are ignored.
+        assert_eq!(
+            first_response(&issue, date("2020-01-03T00:00:00Z")),
+            FirstResponse::Responded {
+                days: 1.0,
+                by: Responder::Bot,
+                first_human_response_days: None,
+            }
+        );
+        assert_eq!(
+            first_response(&issue, date("2020-01-01T18:00:00Z")),
+            FirstResponse::Waiting
+        );
+    }
+
+    #[test]
+    fn test_bot_only_response_is_waiting() {
+        let mut issue = IssueWithTimelineItems {
+            url: "N/A".to_string(),
+            number: 43,
+            title: "Only a bot responds".to_string(),
+            author: Some(Actor {
+                login: "reporter".to_string(),
+                typename: Some("User".to_string()),
+            }),
+            labels: Labels::new(vec![]),
+            created_at: DateTime::from_str("2020-01-01T00:00:00Z").unwrap(),
+            timeline_items: vec![TimelineItem::IssueComment {
+                created_at: DateTime::from_str("2020-01-02T00:00:00Z").unwrap(),
+                author: Some(Actor {
+                    login: "rustbot".to_string(),
+                    typename: Some("Bot".to_string()),
+                }),
+                author_association: Some(AuthorAssociation::None),
+            }],
+        };
+        let as_of = DateTime::from_str("2020-02-01T00:00:00Z").unwrap();
+
+        let response = first_response(&issue, as_of);
+        assert_eq!(
+            response,
+            FirstResponse::Responded {
+                days: 1.0,
+                by: Responder::Bot,
+                first_human_response_days: None,
+            }
+        );
+        assert!(is_waiting_for_human(&issue, &response, as_of));
+
+        issue.timeline_items.push(TimelineItem::ClosedEvent {
+            created_at: DateTime::from_str("2020-01-10T00:00:00Z").unwrap(),
+            actor: None,
+        });
+        assert!(!is_waiting_for_human(
+            &issue,
+            &first_response(&issue, as_of),
+            as_of
+        ));
+
+        // Closed without any response, then reopened.
+        issue.timeline_items.remove(0);
+        issue.timeline_items.push(TimelineItem::ReopenedEvent {
+            created_at: DateTime::from_str("2020-01-20T00:00:00Z").unwrap(),
+            actor: Actor {
+                login: "reporter".to_string(),
+                typename: Some("User".to_string()),
+            },
+        });
+        let response = first_response(&issue, as_of);
+        assert_eq!(response, FirstResponse::ClosedWithoutResponse);
+        assert!(is_waiting_for_human(&issue, &response, as_of));
+    }
+}
@@ -0,0 +1,318 @@
+use chrono::{DateTime, Datelike, FixedOffset};
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+
+use crate::cli::GlobalArgs;
+use crate::*;
+
+/// Compares time-to-close of issues that were labeled `E-needs-mcve` with
+/// similar issues that were not. Issues are similar if they were created in the
+/// same period and have the same subset of `--match-labels`.
+///
+/// Time-to-close is measured from when the issue was created, not from when it
+/// was labeled, until it was last closed. Reopened issues that are open now
+/// count as still open.
+#[derive(clap::Args, Debug)]
+pub struct Args {
+    /// Labels that labeled and unlabeled issues must agree on to be compared.
+    #[arg(long, value_delimiter = ',', default_value = "I-ICE,T-compiler")]
+    match_labels: Vec<String>,
+
+    /// Length of the creation period that issues must agree on to be compared.
+    #[arg(long, default_value = "3", value_parser = clap::value_parser!(u32).range(1..))]
+    period_months: u32,
+
+    /// How many unlabeled issues to pick for each labeled issue.
+    #[arg(long, default_value = "3")]
+    controls_per_issue: usize,
+
+    /// Also write a self-contained HTML report with charts to this file.
+    #[arg(long)]
+    report: Option<PathBuf>,
+}
+
+/// What we need to know about an issue to compare it.
+struct Sample {
+    created_at: DateTime<FixedOffset>,
+    ever_labeled_needs_mcve: bool,
+    /// `None` if the issue is open.
+    days_to_close: Option<f64>,
+}
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct Stratum {
+    period: i32,
+    labels: Vec<String>,
+}
+
+#[derive(serde::Serialize)]
+struct Report {
+    analyzed_issues: usize,
+    labeled: Group,
+    control: Group,
+    /// Labeled vs not labeled.
+    test: MannWhitneyU,
+}
+
+#[derive(Default, serde::Serialize)]
+struct Group {
+    issues: usize,
+    still_open: usize,
+    #[serde(serialize_with = "serialize_as_distribution")]
+    days_to_close: Vec<f64>,
+}
+
+pub async fn run(global: &GlobalArgs, mut args: Args) -> anyhow::Result<()> {
+    for label in &mut args.match_labels {
+        *label = global.label(label).to_owned();
+    }
+
+    let source = global.issue_source()?;
+
+    let strata = RefCell::new(BTreeMap::<Stratum, Vec<Sample>>::new());
+    let analyzed_issues = RefCell::new(0);
+
+    source
+        .for_issues_with_timeline(
+            &issues_query(global, &args)?,
+            |issue| {
+                *analyzed_issues.borrow_mut() += 1;
+                strata
+                    .borrow_mut()
+                    .entry(stratum(issue, &args))
+                    .or_default()
+                    .push(sample(issue));
+            },
+            || eprintln!("Analyzed {} issues", analyzed_issues.borrow()),
+        )
+        .await?;
+
+    let report = compare(
+        analyzed_issues.into_inner(),
+        strata.into_inner(),
+        args.controls_per_issue,
+    );
+    Output::stdout(global.format).print(&report)?;
+
+    if let Some(report_path) = &args.report {
+        write_report(report_path, &report)?;
+    }
+
+    Ok(())
+}
+
+/// The issues that `run` goes through.
+pub fn issues_query(global: &GlobalArgs, _args: &Args) -> anyhow::Result<IssuesQuery> {
+    global.issues_query(serde_json::json!({
+        "timelineItemTypes": ["LABELED_EVENT", "CLOSED_EVENT", "REOPENED_EVENT"],
+    }))
+}
+
+fn write_report(path: &Path, report: &Report) -> anyhow::Result<()> {
+    let mut html = HtmlReport::new("E-needs-mcve control group comparison");
+    html.add_paragraph(&format!(
+        "{} issues analyzed. Mann-Whitney U (labeled vs not labeled): {}",
+        report.analyzed_issues, report.test
+    ));
+    html.add_bar_chart(
+        &format!("Time to close, labeled {E_NEEDS_MCVE}"),
+        &day_histogram(&report.labeled.days_to_close),
+    );
+    html.add_bar_chart(
+        "Time to close, similar but never labeled",
+        &day_histogram(&report.control.days_to_close),
+    );
+
+    eprintln!("Writing report to {path:?}");
+    html.write_to(path)?;
+    Ok(())
+}
+
+fn stratum(issue: &IssueWithTimelineItems, args: &Args) -> Stratum {
+    let months = issue.created_at.year() * 12 + issue.created_at.month0() as i32;
+    Stratum {
+        period: months / args.period_months as i32,
+        labels: args
+            .match_labels
+            .iter()
+            .filter(|name| issue.labels.nodes.iter().any(|label| &label.name == *name))
+            .cloned()
+            .collect(),
+    }
+}
+
+fn sample(issue: &IssueWithTimelineItems) -> Sample {
+    let ever_labeled_needs_mcve = issue.timeline_items.iter().any(|timeline_item| {
+        matches!(
+            timeline_item,
+            TimelineItem::LabeledEvent {
+                label: Label { name },
+                ..
+            } if name == E_NEEDS_MCVE
+        )
+    });
+    // The end of the last period the issue was open, if it is closed now.
+    let closed_at = issue
+        .open_intervals()
+        .last()
+        .and_then(|interval| interval.end);
+
+    Sample {
+        created_at: issue.created_at,
+        ever_labeled_needs_mcve,
+        days_to_close: closed_at.map(|closed_at| days_since(issue, &closed_at)),
+    }
+}
+
+/// For each labeled issue, picks the unlabeled issues of the same stratum that
+/// were created closest in time to it. Each unlabeled issue is picked at most
+/// once.
+fn compare(
+    analyzed_issues: usize,
+    strata: BTreeMap<Stratum, Vec<Sample>>,
+    controls_per_issue: usize,
+) -> Report {
+    let mut labeled = Group::default();
+    let mut control = Group::default();
+
+    for samples in strata.into_values() {
+        let (treated, mut candidates): (Vec<_>, Vec<_>) = samples
+            .into_iter()
+            .partition(|sample| sample.ever_labeled_needs_mcve);
+
+        for sample in treated {
+            for _ in 0..controls_per_issue {
+                let Some(closest) = candidates
+                    .iter()
+                    .enumerate()
+                    .min_by_key(|(_, candidate)| {
+                        (candidate.created_at - sample.created_at)
+                            .num_seconds()
+                            .abs()
+                    })
+                    .map(|(index, _)| index)
+                else {
+                    break;
+                };
+                control.add(&candidates.swap_remove(closest));
+            }
+            labeled.add(&sample);
+        }
+    }
+
+    let test = mann_whitney_u(&labeled.days_to_close, &control.days_to_close).unwrap_or_default();
+    Report {
+        analyzed_issues,
+        labeled,
+        control,
+        test,
+    }
+}
+
+impl Group {
+    fn add(&mut self, sample: &Sample) {
+        self.issues += 1;
+        match sample.days_to_close {
+            Some(days) => self.days_to_close.push(days),
+            None => self.still_open += 1,
+        }
+    }
+}
+
+impl Display for Group {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} issues, {} still open\n    days to close: {}",
+            self.issues,
+            self.still_open,
+            Distribution::from_values(&self.days_to_close).unwrap_or_default()
+        )
+    }
+}
+
+impl Display for Report {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Number of issues analyzed: {}", self.analyzed_issues)?;
+        writeln!(f, "Labeled `{E_NEEDS_MCVE}`: {}", self.labeled)?;
+        writeln!(f, "Similar but never labeled: {}", self.control)?;
+        write!(f, "Mann-Whitney U (labeled vs not labeled): {}", self.test)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    fn sample_at(created_at: &str, ever_labeled_needs_mcve: bool, days_to_close: f64) -> Sample {
+        Sample {
+            created_at: DateTime::from_str(created_at).unwrap(),
+            ever_labeled_needs_mcve,
+            days_to_close: Some(days_to_close),
+        }
+    }
+
+    #[test]
+    fn test_sample_reopened() {
+        let at = |s| DateTime::from_str(s).unwrap();
+        let closed = |created_at| TimelineItem::ClosedEvent {
+            created_at: at(created_at),
+            actor: None,
+        };
+        let mut issue = IssueWithTimelineItems {
+            url: "N/A".to_string(),
+            number: 1,
+            title: "Reopened".to_string(),
+            author: None,
+            labels: Labels::new(vec![]),
+            created_at: at("2020-01-01T00:00:00Z"),
+            timeline_items: vec![
+                closed("2020-01-02T00:00:00Z"),
+                TimelineItem::ReopenedEvent {
+                    created_at: at("2020-01-03T00:00:00Z"),
+                    actor: Actor {
+                        login: "someone".to_string(),
+                        typename: None,
+                    },
+                },
+            ],
+        };
+        assert_eq!(sample(&issue).days_to_close, None);
+
+        issue.timeline_items.push(closed("2020-01-11T00:00:00Z"));
+        assert_eq!(sample(&issue).days_to_close, Some(10.0));
+    }
+
+    #[test]
+    fn test_picks_closest_controls_within_stratum() {
+        let mut strata = BTreeMap::new();
+        strata.insert(
+            Stratum {
+                period: 0,
+                labels: vec![],
+            },
+            vec![
+                sample_at("2020-01-10T00:00:00Z", true, 10.0),
+                sample_at("2020-01-01T00:00:00Z", false, 1.0),
+                sample_at("2020-01-09T00:00:00Z", false, 9.0),
+                sample_at("2020-01-12T00:00:00Z", false, 12.0),
+            ],
+        );
+        strata.insert(
+            Stratum {
+                period: 0,
+                labels: vec!["I-ICE".to_string()],
+            },
+            vec![sample_at("2020-01-10T00:00:00Z", false, 100.0)],
+        );
+
+        let report = compare(5, strata, 2);
+
+        assert_eq!(report.labeled.days_to_close, vec![10.0]);
+        assert_eq!(report.control.days_to_close, vec![9.0, 12.0]);
+    }
+}
@@ -0,0 +1,596 @@
+use anyhow::Context;
+use chrono::{DateTime, Datelike, FixedOffset};
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+use crate::cli::{GlobalArgs, Source};
+use crate::*;
+
+/// Measures how long it takes for `E-needs-mcve` to be resolved, i.e. for the
+/// label to be removed or the issue to be closed.
+#[derive(clap::Args, Debug)]
+pub struct Args {
+    /// To sanity check the conclusion of the program, the data that the program
+    /// analyses is dumped into this file. One JSON object per line with the
+    /// analyzed issue and the conclusion.
+    #[arg(long, default_value = "target/rust-issue-stats/issues.data-dump.jsonl")]
+    data_dump_path: PathBuf,
+
+    /// Recompute the stats from a data dump instead of querying GitHub.
+    #[arg(long, conflicts_with = "data_dump_path")]
+    from_dump: Option<PathBuf>,
+
+    /// Also write a self-contained HTML report with charts to this file.
+    #[arg(long)]
+    report: Option<PathBuf>,
+}
+
+const SLOTS: i64 = 10;
+
+/// Printed once all issues are analyzed. With `--format text`, also printed to
+/// stderr after each page to show progress.
+#[derive(Default, serde::Serialize)]
+struct Stats {
+    analyzed_issues: usize,
+    needs_mcve_issues: usize,
+    needs_mcve_issues_resolved_after_weeks: [u64; SLOTS as usize],
+}
+
+/// What goes into the HTML report in addition to [`Stats`].
+#[derive(Default)]
+struct ReportData {
+    /// Per year the issue was created.
+    cohorts: BTreeMap<i32, Cohort>,
+    labeled_issues: Vec<LabeledIssue>,
+}
+
+#[derive(Default)]
+struct Cohort {
+    labeled: u64,
+    resolved: u64,
+}
+
+struct LabeledIssue {
+    number: u32,
+    url: String,
+    title: String,
+    created_at: DateTime<FixedOffset>,
+    conclusion: Conclusion,
+}
+
+/// One line of the data dump.
+#[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+struct DumpEntry {
+    issue: IssueWithTimelineItems,
+    conclusion: Conclusion,
+}
+
+pub async fn run(global: &GlobalArgs, args: Args) -> anyhow::Result<()> {
+    if let Some(dump_path) = &args.from_dump {
+        return reanalyze_dump(dump_path, global, &args);
+    }
+
+    if let Source::Dump(source_path) = &global.source {
+        if same_file(source_path, &args.data_dump_path) {
+            anyhow::bail!(
+                "{source_path:?} would be overwritten by the data dump, use --from-dump to analyze it again"
+            );
+        }
+    }
+
+    eprintln!("Writing data dump to {:?}", args.data_dump_path);
+    std::fs::create_dir_all(args.data_dump_path.parent().unwrap())?;
+    let data_dump_file = std::fs::OpenOptions::new()
+        .create(true)
+        .write(true)
+        .truncate(true)
+        .open(&args.data_dump_path)?;
+
+    let source = global.issue_source()?;
+
+    let stats = RefCell::new(Stats::default());
+    let report_data = RefCell::new(ReportData::default());
+
+    source
+        .for_issues_with_timeline(
+            &issues_query(global, &args)?,
+            |issue| {
+                let conclusion = analyze_issue(issue).unwrap();
+                stats.borrow_mut().add(&conclusion);
+                report_data.borrow_mut().add(issue, &conclusion);
+
+                let entry = DumpEntry {
+                    issue: issue.clone(),
+                    conclusion,
+                };
+                serde_json::to_writer(&data_dump_file, &entry).unwrap();
+                writeln!(&data_dump_file).unwrap();
+            },
+            || {
+                // Records of partial stats would be counted twice by whatever
+                // reads the machine-readable formats.
+                if global.format == OutputFormat::Text {
+                    eprintln!("{}", stats.borrow());
+                }
+            },
+        )
+        .await?;
+
+    Output::stdout(global.format).print(&*stats.borrow())?;
+
+    if let Some(report_path) = &args.report {
+        write_report(report_path, &stats.borrow(), &report_data.borrow())?;
+    }
+
+    Ok(())
+}
+
+/// The issues that `run` goes through.
+pub fn issues_query(global: &GlobalArgs, _args: &Args) -> anyhow::Result<IssuesQuery> {
+    global.issues_query(serde_json::json!({
+        "timelineItemTypes": ["LABELED_EVENT", "UNLABELED_EVENT"],
+    }))
+}
+
+/// Analyzes the issues in a data dump again, and warns about conclusions that
+/// differ from the dumped ones. The dump is filtered like other sources, e.g.
+/// by `--since`.
+fn reanalyze_dump(dump_path: &Path, global: &GlobalArgs, args: &Args) -> anyhow::Result<()> {
+    let query = issues_query(global, args)?;
+    let mut stats = Stats::default();
+    let mut report_data = ReportData::default();
+
+    let reader = BufReader::new(std::fs::File::open(dump_path)?);
+    for (index, line) in reader.lines().enumerate() {
+        let entry: DumpEntry = serde_json::from_str(&line?)
+            .with_context(|| format!("{}:{}", dump_path.display(), index + 1))?;
+        let Some(issue) = apply_query(&query, entry.issue) else {
+            continue;
+        };
+
+        let conclusion = analyze_issue(&issue)?;
+        if conclusion != entry.conclusion {
+            eprintln!(
+                "{}: dumped conclusion \"{}\" but now \"{}\"",
+                issue.url, entry.conclusion, conclusion
+            );
+        }
+        stats.add(&conclusion);
+        report_data.add(&issue, &conclusion);
+    }
+
+    Output::stdout(global.format).print(&stats)?;
+
+    if let Some(report_path) = &args.report {
+        write_report(report_path, &stats, &report_data)?;
+    }
+
+    Ok(())
+}
+
+fn same_file(a: &Path, b: &Path) -> bool {
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+fn write_report(path: &Path, stats: &Stats, data: &ReportData) -> anyhow::Result<()> {
+    let mut report = HtmlReport::new("E-needs-mcve effectiveness");
+    report.add_paragraph(&format!(
+        "{} issues analyzed, {} of which were ever labeled {E_NEEDS_MCVE}.",
+        stats.analyzed_issues, stats.needs_mcve_issues
+    ));
+
+    let weeks: Vec<_> = stats
+        .needs_mcve_issues_resolved_after_weeks
+        .iter()
+        .enumerate()
+        .map(|(slot, count)| {
+            let label = if slot as i64 == SLOTS - 1 {
+                format!("{}+", slot + 1)
+            } else {
+                format!("{}", slot + 1)
+            };
+            (label, *count as f64)
+        })
+        .collect();
+    report.add_bar_chart("Resolved within number of weeks", &weeks);
+
+    let cohorts: Vec<_> = data
+        .cohorts
+        .iter()
+        .map(|(year, cohort)| {
+            let percent = 100.0 * cohort.resolved as f64 / cohort.labeled as f64;
+            (year.to_string(), percent)
+        })
+        .collect();
+    report.add_bar_chart(
+        "Percent of labeled issues resolved, by year created",
+        &cohorts,
+    );
+
+    let rows: Vec<_> = data
+        .labeled_issues
+        .iter()
+        .map(|issue| {
+            vec![
+                Cell::Link {
+                    text: format!("#{}", issue.number),
+                    url: issue.url.clone(),
+                    sort: Some(issue.number),
+                },
+                Cell::Text(issue.title.clone()),
+                Cell::Text(issue.created_at.format("%Y-%m-%d").to_string()),
+                match issue.conclusion {
+                    Conclusion::NeedsMcveResolvedAfterWeeks(weeks) => Cell::Number(weeks as f64),
+                    _ => Cell::Text(String::new()),
+                },
+                Cell::Text(issue.conclusion.to_string()),
+            ]
+        })
+        .collect();
+    report.add_table(
+        "Issues labeled E-needs-mcve",
+        &["Issue", "Title", "Created", "Weeks", "Conclusion"],
+        &rows,
+    );
+
+    eprintln!("Writing report to {path:?}");
+    report.write_to(path)?;
+    Ok(())
+}
+
+impl ReportData {
+    fn add(&mut self, issue: &IssueWithTimelineItems, conclusion: &Conclusion) {
+        if !conclusion.ever_labeled_needs_mcve() {
+            return;
+        }
+
+        let cohort = self.cohorts.entry(issue.created_at.year()).or_default();
+        cohort.labeled += 1;
+        if let Conclusion::NeedsMcveResolvedAfterWeeks(_) = conclusion {
+            cohort.resolved += 1;
+        }
+
+        self.labeled_issues.push(LabeledIssue {
+            number: issue.number,
+            url: issue.url.clone(),
+            title: issue.title.clone(),
+            created_at: issue.created_at,
+            conclusion: conclusion.clone(),
+        });
+    }
+}
+
+impl Stats {
+    fn add(&mut self, conclusion: &Conclusion) {
+        self.analyzed_issues += 1;
+        if conclusion.ever_labeled_needs_mcve() {
+            self.needs_mcve_issues += 1;
+        }
+        if let Conclusion::NeedsMcveResolvedAfterWeeks(weeks) = *conclusion {
+            let slot = weeks.min(SLOTS - 1);
+            self.needs_mcve_issues_resolved_after_weeks[slot as usize] += 1;
+        }
+    }
+}
+
+impl Display for Stats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Number of issues analyzed:\x20{}\n\
+             \x20                 of these, {} was ever labeled `E-needs-mcve`\n\
+             \x20                 of these, {} was resolved (unlabeled or closed) within the first week.\n\
+             \x20                           {} was resolved within 2 weeks.\n\
+             \x20                           {} was resolved within 3 weeks.\n\
+             \x20                           {} was resolved within 4 weeks.\n\
+             \x20                           {} was resolved within 5 weeks.\n\
+             \x20                           {} was resolved within 6 weeks.\n\
+             \x20                           {} was resolved within 7 weeks.\n\
+             \x20                           {} was resolved within 8 weeks.\n\
+             \x20                           {} was resolved within 9 weeks.\n\
+             \x20                           {} was resolved within 10 weeks or longer.\n\
+            ",
+            self.analyzed_issues,
+            self.needs_mcve_issues,
+            self.needs_mcve_issues_resolved_after_weeks[0],
+            self.needs_mcve_issues_resolved_after_weeks[1],
+            self.needs_mcve_issues_resolved_after_weeks[2],
+            self.needs_mcve_issues_resolved_after_weeks[3],
+            self.needs_mcve_issues_resolved_after_weeks[4],
+            self.needs_mcve_issues_resolved_after_weeks[5],
+            self.needs_mcve_issues_resolved_after_weeks[6],
+            self.needs_mcve_issues_resolved_after_weeks[7],
+            self.needs_mcve_issues_resolved_after_weeks[8],
+            self.needs_mcve_issues_resolved_after_weeks[9],
+        )
+    }
+}
+
+#[derive(PartialEq, PartialOrd, Ord, Eq, Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub enum Conclusion {
+    /// The issue never had the `E-needs-mcve` label.
+    NeverLabeledNeedsMcve,
+
+    /// Was labeled `E-needs-mcve` at the given date
+    LabeledNeedsMcveAt(DateTime<FixedOffset>),
+
+    /// The `E-needs-mcve` was resolved after the given number of weeks since it
+    /// was added. "Resolved" means either that the label was removed or that
+    /// the issue was closed.
+    NeedsMcveResolvedAfterWeeks(i64),
+}
+
+pub fn analyze_issue(issue: &IssueWithTimelineItems) -> anyhow::Result<Conclusion> {
+    let mut state = IssueState::NeverLabeledNeedsMcve;
+
+    for timeline_item in &issue.timeline_items {
+        match state {
+            IssueState::NeverLabeledNeedsMcve => {
+                if let TimelineItem::LabeledEvent {
+                    label: Label { name },
+                    created_at,
+                    ..
+                } = timeline_item
+                {
+                    if name == E_NEEDS_MCVE {
+                        state = IssueState::LabeledNeedsMcveDate(*created_at);
+                    }
+                }
+            }
+            IssueState::LabeledNeedsMcveDate(date) => {
+                let resolved = if let TimelineItem::UnlabeledEvent {
+                    label: Label { name },
+                    created_at,
+                    ..
+                } = timeline_item
+                {
+                    if name == E_NEEDS_MCVE {
+                        Some(created_at)
+                    } else {
+                        None
+                    }
+                } else if let TimelineItem::ClosedEvent { created_at, .. } = timeline_item {
+                    Some(created_at)
+                } else {
+                    None
+                };
+
+                if let Some(resolved) = resolved {
+                    let duration = resolved.signed_duration_since(date);
+                    return Ok(Conclusion::NeedsMcveResolvedAfterWeeks(
+                        duration.num_weeks(),
+                    ));
+                }
+            }
+        }
+    }
+
+    Ok(state.into_conclusion())
+}
+
+impl Conclusion {
+    pub fn ever_labeled_needs_mcve(&self) -> bool {
+        match self {
+            Conclusion::NeverLabeledNeedsMcve => false,
+            Conclusion::NeedsMcveResolvedAfterWeeks(_) => true,
+            Conclusion::LabeledNeedsMcveAt(_) => true,
+        }
+    }
+}
+
+impl Display for Conclusion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Conclusion::NeverLabeledNeedsMcve => f.write_str("Never labeled E-needs-mcve"),
+            Conclusion::LabeledNeedsMcveAt(date) => {
+                write!(
+                    f,
+                    "Labeled E-needs-mcve at {} but never resolved",
+                    date.format("%Y-%m-%d")
+                )
+            }
+            Conclusion::NeedsMcveResolvedAfterWeeks(weeks) => {
+                write!(f, "Resolved E-needs-mcve after {} weeks", weeks)
+            }
+        }
+    }
+}
+
+enum IssueState {
+    NeverLabeledNeedsMcve,
+    LabeledNeedsMcveDate(DateTime<FixedOffset>),
+}
+
+impl IssueState {
+    fn into_conclusion(self) -> Conclusion {
+        match self {
+            IssueState::NeverLabeledNeedsMcve => Conclusion::NeverLabeledNeedsMcve,
+            IssueState::LabeledNeedsMcveDate(date) => Conclusion::LabeledNeedsMcveAt(date),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    const NO_LABELS: Labels = Labels::new(vec![]);
+
+    /// The schema of the machine-readable output. Changing this breaks users.
+    #[test]
+    fn test_stats_output_schema() {
+        let stats = Stats {
+            analyzed_issues: 20,
+            needs_mcve_issues: 3,
+            needs_mcve_issues_resolved_after_weeks: [1, 0, 0, 0, 0, 0, 0, 0, 0, 1],
+        };
+
+        let mut json = Output::new(OutputFormat::Json, vec![]);
+        json.print(&stats).unwrap();
+        assert_eq!(
+            String::from_utf8(json.into_inner()).unwrap(),
+            r#"{"analyzed_issues":20,"needs_mcve_issues":3,"needs_mcve_issues_resolved_after_weeks":[1,0,0,0,0,0,0,0,0,1]}
+"#
+        );
+
+        // Arrays like the weeks are a single column with a JSON array, so that
+        // the columns are the same for all records.
+        let mut csv = Output::new(OutputFormat::Csv, vec![]);
+        csv.print(&stats).unwrap();
+        assert_eq!(
+            String::from_utf8(csv.into_inner()).unwrap(),
+            "analyzed_issues,needs_mcve_issues,needs_mcve_issues_resolved_after_weeks\n\
+             20,3,\"[1,0,0,0,0,0,0,0,0,1]\"\n"
+        );
+    }
+
+    #[test]
+    fn test_dump_entry_round_trip() {
+        let entry = DumpEntry {
+            issue: IssueWithTimelineItems {
+                url: "N/A".to_string(),
+                number: 42,
+                title: "Dumped".to_string(),
+                author: None,
+                labels: Labels::new(vec![Label {
+                    name: "C-bug".to_string(),
+                }]),
+                created_at: DateTime::from_str("2020-01-01T00:00:00Z").unwrap(),
+                timeline_items: vec![TimelineItem::LabeledEvent {
+                    label: Label {
+                        name: E_NEEDS_MCVE.to_string(),
+                    },
+                    created_at: DateTime::from_str("2020-01-01T00:00:00Z").unwrap(),
+                    actor: None,
+                }],
+            },
+            conclusion: Conclusion::LabeledNeedsMcveAt(
+                DateTime::from_str("2020-01-01T00:00:00Z").unwrap(),
+            ),
+        };
+
+        let line = serde_json::to_string(&entry).unwrap();
+        assert_eq!(serde_json::from_str::<DumpEntry>(&line).unwrap(), entry);
+    }
+
+    #[test]
+    fn test_not_applicable() {
+        let issue = IssueWithTimelineItems {
+            url: "N/A".to_string(),
+            number: 42,
+            title: "Never labeled E-needs-mcve".to_string(),
+            author: None,
+            labels: NO_LABELS,
+            created_at: DateTime::from_str("2020-01-01T00:00:00Z").unwrap(),
+            timeline_items: vec![TimelineItem::LabeledEvent {
+                label: Label {
+                    name: "foo".to_string(),
+                },
+                created_at: DateTime::from_str("2020-01-01T00:00:00Z").unwrap(),
+                actor: None,
+            }],
+        };
+
+        assert_eq!(
+            analyze_issue(&issue).unwrap(),
+            Conclusion::NeverLabeledNeedsMcve
+        )
+    }
+
+    #[test]
+    fn test_never_resolved() {
+        let issue = IssueWithTimelineItems {
+            url: "N/A".to_string(),
+            number: 42,
+            title: "Labeled E-needs-mcve but never resolved".to_string(),
+            author: None,
+            labels: NO_LABELS,
+            created_at: DateTime::from_str("2020-01-01T00:00:00Z").unwrap(),
+            timeline_items: vec![TimelineItem::LabeledEvent {
+                label: Label {
+                    name: E_NEEDS_MCVE.to_string(),
+                },
+                created_at: DateTime::from_str("2020-01-01T00:00:00Z").unwrap(),
+                actor: None,
+            }],
+        };
+
+        assert_eq!(
+            analyze_issue(&issue).unwrap(),
+            Conclusion::LabeledNeedsMcveAt(
+                DateTime::parse_from_rfc3339("2020-01-01T00:00:00Z").unwrap()
+            )
+        );
+    }
+
+    #[test]
+    fn test_resolved_via_unlabeling() {
+        let issue = IssueWithTimelineItems {
+            url: "N/A".to_string(),
+            number: 42,
+            title: "Labeled E-needs-mcve then unlabeled".to_string(),
+            author: None,
+            labels: NO_LABELS,
+            created_at: DateTime::from_str("2020-01-01T00:00:00Z").unwrap(),
+            timeline_items: vec![
+                TimelineItem::LabeledEvent {
+                    label: Label {
+                        name: E_NEEDS_MCVE.to_string(),
+                    },
+                    created_at: DateTime::from_str("2020-01-01T00:00:00Z").unwrap(),
+                    actor: None,
+                },
+                TimelineItem::UnlabeledEvent {
+                    label: Label {
+                        name: E_NEEDS_MCVE.to_string(),
+                    },
+                    created_at: DateTime::from_str("2020-01-08T00:00:00Z").unwrap(),
+                    actor: None,
+                },
+            ],
+        };
+
+        assert_eq!(
+            analyze_issue(&issue).unwrap(),
+            Conclusion::NeedsMcveResolvedAfterWeeks(1)
+        );
+    }
+
+    #[test]
+    fn test_resolved_via_close() {
+        let issue = IssueWithTimelineItems {
+            url: "N/A".to_string(),
+            number: 42,
+            title: "Labeled E-needs-mcve but never resolved".to_string(),
+            author: None,
+            labels: NO_LABELS,
+            created_at: DateTime::from_str("2020-01-01T00:00:00Z").unwrap(),
+            timeline_items: vec![
+                TimelineItem::LabeledEvent {
+                    label: Label {
+                        name: E_NEEDS_MCVE.to_string(),
+                    },
+                    created_at: DateTime::from_str("2020-01-01T00:00:00Z").unwrap(),
+                    actor: None,
+                },
+                TimelineItem::ClosedEvent {
+                    created_at: DateTime::from_str("2020-01-15T00:00:00Z").unwrap(),
+                    actor: None,
+                },
+            ],
+        };
+
+        assert_eq!(
+            analyze_issue(&issue).unwrap(),
+            Conclusion::NeedsMcveResolvedAfterWeeks(2)
+        );
+    }
+}
@@ -0,0 +1,12 @@
+//! The subcommands of `rust-issue-stats`. Each has its own `Args` and a `run`
+//! function that is given the [`GlobalArgs`](crate::cli::GlobalArgs) too.
+
+pub mod backlog;
+pub mod first_response;
+pub mod mcve_control_group;
+pub mod mcve_effectiveness;
+pub mod old_mcve;
+pub mod stale_policy;
+pub mod triage;
+pub mod validate;
+pub mod with_event;
@@ -0,0 +1,345 @@
+use anyhow::Context;
+use chrono::{DateTime, FixedOffset, Utc};
+use std::cell::RefCell;
+use std::fmt::Display;
+use std::path::PathBuf;
+
+use crate::cli::{GlobalArgs, Source};
+use crate::*;
+
+/// Lists open `E-needs-mcve` issues whose label and last comment are old, and
+/// that nobody has marked as triaged. With `--as-of`, lists the issues that
+/// would have been listed at that time.
+///
+/// With `--comment` or `--close`, the listed issues are triaged too. That is
+/// only printed unless `--apply` is given.
+#[derive(clap::Args, Debug)]
+#[command(group(clap::ArgGroup::new("action").multiple(true).args(["comment", "close"])))]
+pub struct Args {
+    #[arg(long, default_value = "36")]
+    label_months_considered_old: i64,
+
+    #[arg(long, default_value = "36")]
+    last_comment_months_considered_old: i64,
+
+    /// Issues with a label with this suffix, such as `S-triaged`, are not
+    /// listed.
+    #[arg(long, default_value = "triaged")]
+    triaged_label_suffix: String,
+
+    /// Comment on listed issues with this text. `{url}`, `{author}`,
+    /// `{label_months}` and `{last_comment_months}` are replaced.
+    #[arg(long)]
+    comment: Option<String>,
+
+    /// Close listed issues as not planned.
+    #[arg(long)]
+    close: bool,
+
+    /// Really comment on and close issues instead of printing what would be
+    /// done.
+    #[arg(long, requires = "action")]
+    apply: bool,
+
+    /// The maximum number of issues to comment on or close in one run.
+    #[arg(long, default_value = "10")]
+    max_actions: usize,
+
+    /// Where `--apply` appends what it did, one JSON object per line.
+    #[arg(long, default_value = "target/rust-issue-stats/audit.jsonl")]
+    audit_log: PathBuf,
+}
+
+#[derive(serde::Serialize)]
+struct StaleIssue {
+    url: String,
+    title: String,
+    label_months: i64,
+    last_comment_months: i64,
+}
+
+pub async fn run(global: &GlobalArgs, args: Args) -> anyhow::Result<()> {
+    let source = global.issue_source()?;
+
+    let output = RefCell::new(Output::stdout(global.format));
+
+    let mut bulk_triage = bulk_triage(global, &args)?;
+    let to_triage = RefCell::new(vec![]);
+
+    let as_of = global.as_of();
+    // With `--as-of`, the state of issues is reconstructed.
+    let historical = global.as_of.is_some();
+    source
+        .for_issues_with_timeline(
+            &issues_query(global, &args)?,
+            |issue| {
+                let snapshot;
+                let issue = if historical {
+                    match issue.at(as_of) {
+                        Some(issue) if is_open_needs_mcve(&issue, as_of) => {
+                            snapshot = issue;
+                            &snapshot
+                        }
+                        _ => return,
+                    }
+                } else {
+                    issue
+                };
+
+                let (label_age_months, comment_age_months) = match get_ages(issue, as_of) {
+                    Ok(ages) => ages,
+                    Err(e) => {
+                        eprintln!("{}: {e}", issue.url);
+                        return;
+                    }
+                };
+
+                let old_enough = label_age_months > args.label_months_considered_old
+                    && comment_age_months > args.last_comment_months_considered_old;
+
+                let labeled_triaged = issue
+                    .labels
+                    .nodes
+                    .iter()
+                    .any(|label| is_triaged_label(label, &args.triaged_label_suffix));
+
+                if old_enough && !labeled_triaged && !issue.labels.is_complete() {
+                    // The triaged label may be among the labels that are missing.
+                    eprintln!("{}: not all labels are known, skipping", issue.url);
+                    return;
+                }
+
+                if old_enough && !labeled_triaged {
+                    let stale_issue = StaleIssue {
+                        url: issue.url.clone(),
+                        title: issue.title.clone(),
+                        label_months: label_age_months,
+                        last_comment_months: comment_age_months,
+                    };
+                    output.borrow_mut().print(&stale_issue).unwrap();
+                    if bulk_triage.is_some() {
+                        to_triage
+                            .borrow_mut()
+                            .push((issue.clone(), stale_issue.template_values(issue)));
+                    }
+                }
+            },
+            || {},
+        )
+        .await?;
+
+    if let Some(bulk_triage) = &mut bulk_triage {
+        let to_triage = to_triage.into_inner();
+        for (handled, (issue, values)) in to_triage.iter().enumerate() {
+            if !bulk_triage.handle(issue, values).await? {
+                eprintln!(
+                    "Reached --max-actions {}, {} issues left",
+                    args.max_actions,
+                    to_triage.len() - handled
+                );
+                break;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// The issues that `run` goes through.
+pub fn issues_query(global: &GlobalArgs, _args: &Args) -> anyhow::Result<IssuesQuery> {
+    // With `--as-of`, issues that have been closed or unlabeled since then are
+    // needed too.
+    let variables = if global.as_of.is_some() {
+        serde_json::json!({
+            "timelineItemTypes": ["LABELED_EVENT", "UNLABELED_EVENT", "CLOSED_EVENT", "REOPENED_EVENT", "ISSUE_COMMENT"],
+        })
+    } else {
+        serde_json::json!({
+            "states": ["OPEN"],
+            "timelineItemTypes": ["LABELED_EVENT", "UNLABELED_EVENT", "ISSUE_COMMENT"],
+            "filterBy": {
+                "labels": ["E-needs-mcve"]
+            }
+        })
+    };
+    let mut query = global.issues_query(variables)?;
+    let as_of = global.as_of().with_timezone(&Utc);
+    query.created_until = Some(query.created_until.map_or(as_of, |until| until.min(as_of)));
+    Ok(query)
+}
+
+fn bulk_triage(global: &GlobalArgs, args: &Args) -> anyhow::Result<Option<BulkTriage>> {
+    if args.comment.is_none() && !args.close {
+        return Ok(None);
+    }
+    if global.as_of.is_some() {
+        anyhow::bail!("--comment and --close cannot be used with --as-of");
+    }
+    // Issues may have been commented on or closed since cached or dumped data
+    // was fetched.
+    if args.apply && global.cache.is_some() {
+        anyhow::bail!("--apply cannot be used with --cache");
+    }
+    if args.apply && global.source != Source::GitHub {
+        anyhow::bail!("--apply needs --source github");
+    }
+
+    let repository = global.repository()?;
+    let bulk_triage = BulkTriage::dry_run(
+        &repository.owner,
+        &repository.name,
+        TriageActions {
+            comment: args.comment.clone(),
+            close: args.close,
+        },
+        args.max_actions,
+    )
+    .with_required_label(E_NEEDS_MCVE);
+    Ok(Some(if args.apply {
+        bulk_triage.with_apply(global.github()?, args.audit_log.clone())
+    } else {
+        bulk_triage
+    }))
+}
+
+impl StaleIssue {
+    fn template_values(&self, issue: &IssueWithTimelineItems) -> Vec<(&'static str, String)> {
+        vec![
+            ("url", self.url.clone()),
+            (
+                "author",
+                issue
+                    .author
+                    .as_ref()
+                    .map_or_else(|| "ghost".to_owned(), |author| author.login.clone()),
+            ),
+            ("label_months", self.label_months.to_string()),
+            ("last_comment_months", self.last_comment_months.to_string()),
+        ]
+    }
+}
+
+impl Display for StaleIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} E-needs-mcve {} months old, last comment {} months ago",
+            self.url, self.label_months, self.last_comment_months
+        )
+    }
+}
+
+fn is_open_needs_mcve(issue: &IssueWithTimelineItems, as_of: DateTime<FixedOffset>) -> bool {
+    issue.was_open_at(as_of)
+        && issue
+            .labels
+            .nodes
+            .iter()
+            .any(|label| label.name == E_NEEDS_MCVE)
+}
+
+/// Labels such as `triaged` or `S-triaged` mark that someone has looked at the
+/// issue, regardless of category.
+fn is_triaged_label(label: &Label, triaged_label_suffix: &str) -> bool {
+    label.suffix().eq_ignore_ascii_case(triaged_label_suffix)
+}
+
+/// Calendar months since `E-needs-mcve` was last added, and since the last
+/// comment or, without comments, since the issue was created.
+pub fn get_ages(
+    issue: &IssueWithTimelineItems,
+    as_of: DateTime<FixedOffset>,
+) -> anyhow::Result<(i64, i64)> {
+    let mut last_labeled_at = None;
+    let mut last_commented_at = None;
+
+    for timeline_item in &issue.timeline_items {
+        if let TimelineItem::LabeledEvent {
+            label: Label { name },
+            created_at,
+            ..
+        } = timeline_item
+        {
+            if name == E_NEEDS_MCVE {
+                last_labeled_at = Some(*created_at);
+            }
+        }
+        if let TimelineItem::IssueComment { created_at, .. } = timeline_item {
+            last_commented_at = Some(*created_at);
+        }
+    }
+
+    let last_labeled_at =
+        last_labeled_at.with_context(|| format!("never labeled {E_NEEDS_MCVE}"))?;
+    let label_age = months_between(last_labeled_at, as_of);
+    let last_comment_age = months_between(last_commented_at.unwrap_or(issue.created_at), as_of);
+    Ok((label_age, last_comment_age))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The schema of the machine-readable output. Changing this breaks users.
+    #[test]
+    fn test_stale_issue_output_schema() {
+        let stale_issue = StaleIssue {
+            url: "https://github.com/rust-lang/rust/issues/1".to_string(),
+            title: "Title, with comma".to_string(),
+            label_months: 40,
+            last_comment_months: 38,
+        };
+
+        let mut json = Output::new(OutputFormat::Json, vec![]);
+        json.print(&stale_issue).unwrap();
+        assert_eq!(
+            String::from_utf8(json.into_inner()).unwrap(),
+            r#"{"url":"https://github.com/rust-lang/rust/issues/1","title":"Title, with comma","label_months":40,"last_comment_months":38}
+"#
+        );
+
+        let mut csv = Output::new(OutputFormat::Csv, vec![]);
+        csv.print(&stale_issue).unwrap();
+        assert_eq!(
+            String::from_utf8(csv.into_inner()).unwrap(),
+            "url,title,label_months,last_comment_months\n\
+             https://github.com/rust-lang/rust/issues/1,\"Title, with comma\",40,38\n"
+        );
+    }
+
+    #[test]
+    fn test_get_ages() {
+        let at = |s| parse_timestamp(s).unwrap();
+        let issue = IssueWithTimelineItems {
+            url: "N/A".to_string(),
+            number: 1,
+            title: "Title".to_string(),
+            author: None,
+            labels: Labels::new(vec![]),
+            created_at: at("2019-01-01"),
+            timeline_items: vec![
+                TimelineItem::LabeledEvent {
+                    created_at: at("2020-01-31"),
+                    label: Label {
+                        name: E_NEEDS_MCVE.to_string(),
+                    },
+                    actor: None,
+                },
+                TimelineItem::IssueComment {
+                    created_at: at("2020-03-15"),
+                    author: None,
+                    author_association: None,
+                },
+            ],
+        };
+
+        assert_eq!(get_ages(&issue, at("2023-01-30")).unwrap(), (35, 34));
+        assert_eq!(get_ages(&issue, at("2023-01-31")).unwrap(), (36, 34));
+
+        let unlabeled = IssueWithTimelineItems {
+            timeline_items: vec![],
+            ..issue
+        };
+        assert!(get_ages(&unlabeled, at("2023-01-31")).is_err());
+    }
+}
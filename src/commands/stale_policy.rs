@@ -0,0 +1,306 @@
+use chrono::{DateTime, Datelike, FixedOffset, Months, NaiveDate};
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+
+use super::old_mcve::get_ages;
+use crate::cli::GlobalArgs;
+use crate::*;
+
+/// Simulates an auto-close policy for `E-needs-mcve`: on the first day of each
+/// month, close open issues whose label and last non-bot comment are old.
+/// Reports how many issues would have been closed per month, and how many of
+/// those got `E-needs-mcve` removed later while still open, i.e. would have
+/// been closed wrongly.
+#[derive(clap::Args, Debug)]
+pub struct Args {
+    #[arg(long, default_value = "36")]
+    label_months_considered_old: i64,
+
+    /// Comments by bots do not count.
+    #[arg(long, default_value = "36")]
+    last_comment_months_considered_old: i64,
+
+    /// Also write a self-contained HTML report with charts to this file.
+    #[arg(long)]
+    report: Option<PathBuf>,
+}
+
+/// What the policy would have done with one issue.
+#[derive(Debug, PartialEq)]
+pub struct SimulatedClose {
+    at: DateTime<FixedOffset>,
+    /// `E-needs-mcve` was removed later while the issue was open.
+    resolved_anyway: bool,
+}
+
+#[derive(Debug, PartialEq, serde::Serialize)]
+pub struct PolicyMonth {
+    /// The day of the policy run.
+    month: NaiveDate,
+    closed: u64,
+    resolved_anyway: u64,
+}
+
+pub async fn run(global: &GlobalArgs, args: Args) -> anyhow::Result<()> {
+    let source = global.issue_source()?;
+
+    let closed = RefCell::new(BTreeMap::<NaiveDate, (u64, u64)>::new());
+    let analyzed_issues = RefCell::new(0);
+
+    let as_of = global.as_of();
+    source
+        .for_issues_with_timeline(
+            &issues_query(global, &args)?,
+            |issue| {
+                *analyzed_issues.borrow_mut() += 1;
+                if let Some(simulated) = simulate(issue, &args, as_of) {
+                    let mut closed = closed.borrow_mut();
+                    let counts = closed.entry(simulated.at.date_naive()).or_default();
+                    counts.0 += 1;
+                    counts.1 += simulated.resolved_anyway as u64;
+                }
+            },
+            || eprintln!("Analyzed {} issues", analyzed_issues.borrow()),
+        )
+        .await?;
+
+    let rows = rows(&closed.into_inner());
+    let mut output = Output::stdout(global.format);
+    for row in &rows {
+        output.print(row)?;
+    }
+
+    if let Some(report_path) = &args.report {
+        write_report(report_path, &rows)?;
+    }
+
+    Ok(())
+}
+
+/// The issues that `run` goes through.
+pub fn issues_query(global: &GlobalArgs, _args: &Args) -> anyhow::Result<IssuesQuery> {
+    // Issues that have been closed or unlabeled since are needed too, so
+    // neither `states` nor `filterBy` are used.
+    global.issues_query(serde_json::json!({
+        "timelineItemTypes": ["LABELED_EVENT", "UNLABELED_EVENT", "CLOSED_EVENT", "REOPENED_EVENT", "ISSUE_COMMENT"],
+    }))
+}
+
+fn write_report(path: &Path, rows: &[PolicyMonth]) -> anyhow::Result<()> {
+    let mut report = HtmlReport::new("Simulated E-needs-mcve auto-close policy");
+    let closed: u64 = rows.iter().map(|row| row.closed).sum();
+    let resolved_anyway: u64 = rows.iter().map(|row| row.resolved_anyway).sum();
+    report.add_paragraph(&format!(
+        "{closed} issues would have been closed, {resolved_anyway} of which were resolved anyway."
+    ));
+    let series = |value: fn(&PolicyMonth) -> u64| -> Vec<(String, f64)> {
+        rows.iter()
+            .map(|row| (row.month.format("%Y-%m").to_string(), value(row) as f64))
+            .collect()
+    };
+    report.add_bar_chart("Closed", &series(|row| row.closed));
+    report.add_bar_chart("Closed wrongly", &series(|row| row.resolved_anyway));
+
+    eprintln!("Writing report to {path:?}");
+    report.write_to(path)?;
+    Ok(())
+}
+
+/// The first policy run that would have closed `issue`, using the same ages as
+/// `old-mcve` but without bot comments. Runs after `as_of` are not simulated.
+pub fn simulate(
+    issue: &IssueWithTimelineItems,
+    args: &Args,
+    as_of: DateTime<FixedOffset>,
+) -> Option<SimulatedClose> {
+    let labeled = issue.label_intervals(E_NEEDS_MCVE);
+    let first_labeled_at = labeled.first()?.start;
+
+    let mut run_at = start_of_next_month(first_labeled_at);
+    while run_at <= as_of {
+        if let Some(mut snapshot) = issue.at(run_at) {
+            if would_close(&mut snapshot, args, run_at) {
+                let resolved_anyway = labeled
+                    .iter()
+                    .find(|interval| interval.contains(run_at))
+                    .and_then(|interval| interval.end)
+                    .is_some_and(|end| end <= as_of && issue.was_open_at(end));
+                return Some(SimulatedClose {
+                    at: run_at,
+                    resolved_anyway,
+                });
+            }
+        }
+        run_at = run_at + Months::new(1);
+    }
+    None
+}
+
+fn would_close(
+    snapshot: &mut IssueWithTimelineItems,
+    args: &Args,
+    run_at: DateTime<FixedOffset>,
+) -> bool {
+    if !snapshot.was_open_at(run_at)
+        || !snapshot
+            .labels
+            .nodes
+            .iter()
+            .any(|label| label.name == E_NEEDS_MCVE)
+    {
+        return false;
+    }
+
+    snapshot.timeline_items.retain(|item| {
+        !matches!(item, TimelineItem::IssueComment { author: Some(author), .. } if author.is_bot())
+    });
+    get_ages(snapshot, run_at).is_ok_and(|(label_age_months, comment_age_months)| {
+        label_age_months > args.label_months_considered_old
+            && comment_age_months > args.last_comment_months_considered_old
+    })
+}
+
+fn start_of_next_month(timestamp: DateTime<FixedOffset>) -> DateTime<FixedOffset> {
+    let date = timestamp.date_naive();
+    let first = NaiveDate::from_ymd_opt(date.year(), date.month(), 1).expect("valid date");
+    (first + Months::new(1))
+        .and_hms_opt(0, 0, 0)
+        .expect("midnight exists")
+        .and_utc()
+        .fixed_offset()
+}
+
+/// One row per month from the first to the last month with closed issues.
+fn rows(closed: &BTreeMap<NaiveDate, (u64, u64)>) -> Vec<PolicyMonth> {
+    let (Some(first), Some(last)) = (closed.keys().next(), closed.keys().next_back()) else {
+        return vec![];
+    };
+
+    let mut rows = vec![];
+    let mut month = *first;
+    while month <= *last {
+        let (closed, resolved_anyway) = closed.get(&month).copied().unwrap_or_default();
+        rows.push(PolicyMonth {
+            month,
+            closed,
+            resolved_anyway,
+        });
+        month = month + Months::new(1);
+    }
+    rows
+}
+
+impl Display for PolicyMonth {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} closed {} ({} resolved anyway)",
+            self.month.format("%Y-%m"),
+            self.closed,
+            self.resolved_anyway
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn label_event(labeled: bool, created_at: &str) -> TimelineItem {
+        let label = Label {
+            name: E_NEEDS_MCVE.to_string(),
+        };
+        let created_at = parse_timestamp(created_at).unwrap();
+        if labeled {
+            TimelineItem::LabeledEvent {
+                label,
+                created_at,
+                actor: None,
+            }
+        } else {
+            TimelineItem::UnlabeledEvent {
+                label,
+                created_at,
+                actor: None,
+            }
+        }
+    }
+
+    fn comment(created_at: &str, login: &str) -> TimelineItem {
+        TimelineItem::IssueComment {
+            created_at: parse_timestamp(created_at).unwrap(),
+            author: Some(Actor {
+                typename: None,
+                login: login.to_string(),
+            }),
+            author_association: None,
+        }
+    }
+
+    #[test]
+    fn test_simulate() {
+        let at = |s| parse_timestamp(s).unwrap();
+        let args = Args {
+            label_months_considered_old: 2,
+            last_comment_months_considered_old: 3,
+            report: None,
+        };
+        let issue = IssueWithTimelineItems {
+            url: "N/A".to_string(),
+            number: 1,
+            title: "Title".to_string(),
+            author: None,
+            labels: Labels::new(vec![]),
+            created_at: at("2020-01-01"),
+            timeline_items: vec![
+                label_event(true, "2020-01-15"),
+                comment("2020-03-01", "someone"),
+                comment("2020-06-01", "rustbot"),
+                label_event(false, "2020-09-01"),
+            ],
+        };
+
+        // The label is old enough from May, the human comment from July.
+        assert_eq!(
+            simulate(&issue, &args, at("2021-01-01")),
+            Some(SimulatedClose {
+                at: at("2020-07-01"),
+                resolved_anyway: true,
+            })
+        );
+        assert_eq!(
+            simulate(&issue, &args, at("2020-08-01")),
+            Some(SimulatedClose {
+                at: at("2020-07-01"),
+                resolved_anyway: false,
+            })
+        );
+        assert_eq!(simulate(&issue, &args, at("2020-06-30")), None);
+
+        assert_eq!(
+            rows(&BTreeMap::from([
+                (at("2020-07-01").date_naive(), (2, 1)),
+                (at("2020-09-01").date_naive(), (1, 0)),
+            ])),
+            vec![
+                PolicyMonth {
+                    month: at("2020-07-01").date_naive(),
+                    closed: 2,
+                    resolved_anyway: 1,
+                },
+                PolicyMonth {
+                    month: at("2020-08-01").date_naive(),
+                    closed: 0,
+                    resolved_anyway: 0,
+                },
+                PolicyMonth {
+                    month: at("2020-09-01").date_naive(),
+                    closed: 1,
+                    resolved_anyway: 0,
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,346 @@
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+
+use crate::cli::GlobalArgs;
+use crate::*;
+
+const NEEDS_TRIAGE: &str = "needs-triage";
+
+/// Reports how long it takes for issues to get triaged, i.e. to get a team
+/// label (T-*), an area label (A-*) or a kind label (C-*), and for
+/// `needs-triage` to be removed.
+#[derive(clap::Args, Debug)]
+pub struct Args {
+    /// Open untriaged issues older than this are listed.
+    #[arg(long, default_value = "30")]
+    untriaged_days_considered_old: i64,
+
+    /// Also write a self-contained HTML report with charts to this file.
+    #[arg(long)]
+    report: Option<PathBuf>,
+}
+
+/// Days since the issue was created. `None` if it has not happened (yet).
+#[derive(Debug, Default, PartialEq)]
+pub struct TriageLatencies {
+    /// The first team label, e.g. `compiler` for `T-compiler`.
+    team: Option<String>,
+    team_label: Option<f64>,
+    area_label: Option<f64>,
+    kind_label: Option<f64>,
+    needs_triage_removed: Option<f64>,
+}
+
+#[derive(Default, serde::Serialize)]
+struct Stats {
+    analyzed_issues: usize,
+    #[serde(serialize_with = "serialize_as_distribution")]
+    team_label_days: Vec<f64>,
+    /// An array rather than a map so that CSV columns do not depend on the data.
+    #[serde(serialize_with = "serialize_per_team")]
+    team_label_days_per_team: BTreeMap<String, Vec<f64>>,
+    #[serde(serialize_with = "serialize_as_distribution")]
+    area_label_days: Vec<f64>,
+    #[serde(serialize_with = "serialize_as_distribution")]
+    kind_label_days: Vec<f64>,
+    #[serde(serialize_with = "serialize_as_distribution")]
+    needs_triage_removed_days: Vec<f64>,
+    untriaged: Vec<UntriagedIssue>,
+}
+
+#[derive(serde::Serialize)]
+struct TeamLabelDays<'a> {
+    team: &'a str,
+    days: Distribution,
+}
+
+#[derive(serde::Serialize)]
+struct UntriagedIssue {
+    number: u32,
+    url: String,
+    title: String,
+    days: i64,
+}
+
+pub async fn run(global: &GlobalArgs, args: Args) -> anyhow::Result<()> {
+    let source = global.issue_source()?;
+
+    let stats = RefCell::new(Stats::default());
+
+    let as_of = global.as_of();
+    source
+        .for_issues_with_timeline(
+            &issues_query(global, &args)?,
+            |issue| {
+                let mut stats = stats.borrow_mut();
+                stats.analyzed_issues += 1;
+
+                let latencies = triage_latencies(issue);
+                stats.team_label_days.extend(latencies.team_label);
+                if let (Some(team), Some(days)) = (latencies.team, latencies.team_label) {
+                    stats
+                        .team_label_days_per_team
+                        .entry(team)
+                        .or_default()
+                        .push(days);
+                }
+                stats.area_label_days.extend(latencies.area_label);
+                stats.kind_label_days.extend(latencies.kind_label);
+                stats
+                    .needs_triage_removed_days
+                    .extend(latencies.needs_triage_removed);
+
+                let days = as_of.signed_duration_since(issue.created_at).num_days();
+                if issue.was_open_at(as_of)
+                    && is_untriaged(issue, as_of)
+                    && days > args.untriaged_days_considered_old
+                {
+                    stats.untriaged.push(UntriagedIssue {
+                        number: issue.number,
+                        url: issue.url.clone(),
+                        title: issue.title.clone(),
+                        days,
+                    });
+                }
+            },
+            || eprintln!("Analyzed {} issues", stats.borrow().analyzed_issues),
+        )
+        .await?;
+
+    let stats = stats.into_inner();
+    Output::stdout(global.format).print(&stats)?;
+
+    if let Some(report_path) = &args.report {
+        write_report(report_path, &stats)?;
+    }
+
+    Ok(())
+}
+
+/// The issues that `run` goes through.
+pub fn issues_query(global: &GlobalArgs, _args: &Args) -> anyhow::Result<IssuesQuery> {
+    global.issues_query(serde_json::json!({
+        "timelineItemTypes": ["LABELED_EVENT", "UNLABELED_EVENT", "CLOSED_EVENT", "REOPENED_EVENT"],
+    }))
+}
+
+fn write_report(path: &Path, stats: &Stats) -> anyhow::Result<()> {
+    let mut report = HtmlReport::new("Time to triage");
+    report.add_paragraph(&format!("{} issues analyzed.", stats.analyzed_issues));
+    report.add_bar_chart(
+        "Time until first T-* label",
+        &day_histogram(&stats.team_label_days),
+    );
+    report.add_bar_chart(
+        "Time until `needs-triage` was removed",
+        &day_histogram(&stats.needs_triage_removed_days),
+    );
+
+    let per_team: Vec<_> = stats
+        .team_label_days_per_team
+        .iter()
+        .filter_map(|(team, days)| {
+            Distribution::from_values(days).map(|d| (format!("T-{team}"), d.median))
+        })
+        .collect();
+    report.add_bar_chart("Median days until first T-* label, per team", &per_team);
+
+    let rows: Vec<_> = stats
+        .untriaged
+        .iter()
+        .map(|untriaged| {
+            vec![
+                Cell::Link {
+                    text: format!("#{}", untriaged.number),
+                    url: untriaged.url.clone(),
+                    sort: Some(untriaged.number),
+                },
+                Cell::Text(untriaged.title.clone()),
+                Cell::Number(untriaged.days as f64),
+            ]
+        })
+        .collect();
+    report.add_table(
+        "Open untriaged issues",
+        &["Issue", "Title", "Days untriaged"],
+        &rows,
+    );
+
+    eprintln!("Writing report to {path:?}");
+    report.write_to(path)?;
+    Ok(())
+}
+
+pub fn triage_latencies(issue: &IssueWithTimelineItems) -> TriageLatencies {
+    let mut latencies = TriageLatencies::default();
+
+    for timeline_item in &issue.timeline_items {
+        let (label, created_at, labeled) = match timeline_item {
+            TimelineItem::LabeledEvent {
+                label, created_at, ..
+            } => (label, created_at, true),
+            TimelineItem::UnlabeledEvent {
+                label, created_at, ..
+            } => (label, created_at, false),
+            _ => continue,
+        };
+
+        let latency = match (labeled, label.category()) {
+            (true, LabelCategory::Team) => {
+                latencies
+                    .team
+                    .get_or_insert_with(|| label.suffix().to_owned());
+                &mut latencies.team_label
+            }
+            (true, LabelCategory::Area) => &mut latencies.area_label,
+            (true, LabelCategory::Category) => &mut latencies.kind_label,
+            (false, _) if label.name == NEEDS_TRIAGE => &mut latencies.needs_triage_removed,
+            _ => continue,
+        };
+        latency.get_or_insert(days_since(issue, created_at));
+    }
+
+    latencies
+}
+
+/// An issue is untriaged if it still had `needs-triage` or had no team yet at
+/// the given point in time.
+fn is_untriaged(issue: &IssueWithTimelineItems, as_of: DateTime<FixedOffset>) -> bool {
+    let labels = issue.labels_at(as_of);
+    labels.iter().any(|label| label.name == NEEDS_TRIAGE)
+        || !labels
+            .iter()
+            .any(|label| label.category() == LabelCategory::Team)
+}
+
+fn serialize_per_team<S: serde::Serializer>(
+    per_team: &BTreeMap<String, Vec<f64>>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_seq(per_team.iter().map(|(team, days)| TeamLabelDays {
+        team,
+        days: Distribution::from_values(days).unwrap_or_default(),
+    }))
+}
+
+impl Display for Stats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Number of issues analyzed: {}", self.analyzed_issues)?;
+        writeln!(f, "Days until")?;
+        write_distribution(f, "    first T-* label        ", &self.team_label_days)?;
+        for (team, days) in &self.team_label_days_per_team {
+            write_distribution(f, &format!("        T-{team}"), days)?;
+        }
+        write_distribution(f, "    first A-* label        ", &self.area_label_days)?;
+        write_distribution(f, "    first C-* label        ", &self.kind_label_days)?;
+        write_distribution(
+            f,
+            "    `needs-triage` removed ",
+            &self.needs_triage_removed_days,
+        )?;
+        writeln!(f, "Open untriaged issues:")?;
+        for untriaged in &self.untriaged {
+            writeln!(f, "    {untriaged}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for UntriagedIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} untriaged for {} days: {}",
+            self.url, self.days, self.title
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    fn event(labeled: bool, name: &str, created_at: &str) -> TimelineItem {
+        let label = Label {
+            name: name.to_string(),
+        };
+        let created_at = DateTime::from_str(created_at).unwrap();
+        if labeled {
+            TimelineItem::LabeledEvent {
+                label,
+                created_at,
+                actor: None,
+            }
+        } else {
+            TimelineItem::UnlabeledEvent {
+                label,
+                created_at,
+                actor: None,
+            }
+        }
+    }
+
+    #[test]
+    fn test_triage_latencies() {
+        let issue = IssueWithTimelineItems {
+            url: "N/A".to_string(),
+            number: 42,
+            title: "Triaged over a few days".to_string(),
+            author: None,
+            labels: Labels::new(vec![]),
+            created_at: DateTime::from_str("2020-01-01T00:00:00Z").unwrap(),
+            timeline_items: vec![
+                event(true, NEEDS_TRIAGE, "2020-01-01T00:00:00Z"),
+                event(true, "C-bug", "2020-01-01T00:00:00Z"),
+                event(true, "A-diagnostics", "2020-01-02T00:00:00Z"),
+                event(true, "T-compiler", "2020-01-03T00:00:00Z"),
+                event(true, "A-lints", "2020-01-04T00:00:00Z"),
+                event(false, NEEDS_TRIAGE, "2020-01-03T12:00:00Z"),
+            ],
+        };
+
+        assert_eq!(
+            triage_latencies(&issue),
+            TriageLatencies {
+                team: Some("compiler".to_string()),
+                team_label: Some(2.0),
+                area_label: Some(1.0),
+                kind_label: Some(0.0),
+                needs_triage_removed: Some(2.5),
+            }
+        );
+    }
+    #[test]
+    fn test_untriaged_as_of() {
+        let issue = IssueWithTimelineItems {
+            url: "N/A".to_string(),
+            number: 43,
+            title: "Team label added later".to_string(),
+            author: None,
+            labels: Labels::new(vec![Label {
+                name: "T-compiler".to_string(),
+            }]),
+            created_at: DateTime::from_str("2020-01-01T00:00:00Z").unwrap(),
+            timeline_items: vec![event(true, "T-compiler", "2020-03-01T00:00:00Z")],
+        };
+        let before = DateTime::from_str("2020-02-01T00:00:00Z").unwrap();
+        let after = DateTime::from_str("2020-04-01T00:00:00Z").unwrap();
+        assert!(is_untriaged(&issue, before));
+        assert!(!is_untriaged(&issue, after));
+
+        let issue = IssueWithTimelineItems {
+            labels: Labels::new(vec![]),
+            timeline_items: vec![
+                event(true, "T-compiler", "2020-01-02T00:00:00Z"),
+                event(false, "T-compiler", "2020-03-01T00:00:00Z"),
+            ],
+            ..issue
+        };
+        assert!(!is_untriaged(&issue, before));
+        assert!(is_untriaged(&issue, after));
+    }
+}
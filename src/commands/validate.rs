@@ -0,0 +1,61 @@
+use std::cell::RefCell;
+
+use crate::cli::GlobalArgs;
+use crate::*;
+
+/// Lists oddities in the timelines of issues by category, such as labels that
+/// were removed without having been added, issues that were closed twice, and
+/// events from before the issue was created. `--exclude-anomalous` skips these
+/// issues in other subcommands.
+#[derive(clap::Args, Debug)]
+pub struct Args {
+    /// Only list anomalies of these categories. Can be given more than once or
+    /// separated by commas.
+    #[arg(long, value_enum, value_delimiter = ',')]
+    category: Vec<AnomalyCategory>,
+
+    /// Only check issues that currently have this label.
+    #[arg(long)]
+    label: Option<String>,
+}
+
+pub async fn run(global: &GlobalArgs, args: Args) -> anyhow::Result<()> {
+    let source = global.issue_source()?;
+
+    let report = RefCell::new(ValidationReport::default());
+
+    source
+        .for_issues_with_timeline(
+            &issues_query(global, &args)?,
+            |issue| report.borrow_mut().add(issue),
+            || eprintln!("Checked {} issues", report.borrow().issues),
+        )
+        .await?;
+
+    let report = report.into_inner();
+    let mut output = Output::stdout(global.format);
+    for (category, listings) in &report.by_category {
+        if args.category.is_empty() || args.category.contains(category) {
+            for listing in listings {
+                output.print(listing)?;
+            }
+        }
+    }
+    eprintln!("{report}");
+
+    Ok(())
+}
+
+/// The issues that `run` goes through.
+pub fn issues_query(global: &GlobalArgs, args: &Args) -> anyhow::Result<IssuesQuery> {
+    let mut variables = serde_json::json!({
+        "timelineItemTypes": TIMELINE_ITEM_TYPES,
+    });
+    if let Some(label) = &args.label {
+        variables["filterBy"] = serde_json::json!({ "labels": [global.label(label)] });
+    }
+    let mut query = global.issues_query(variables)?;
+    // Otherwise there would be nothing to validate.
+    query.exclude_anomalous = false;
+    Ok(query)
+}
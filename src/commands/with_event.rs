@@ -0,0 +1,282 @@
+use chrono::{DateTime, FixedOffset, NaiveDate};
+use std::cell::RefCell;
+use std::fmt::Display;
+
+use crate::cli::GlobalArgs;
+use crate::*;
+
+/// Lists open issues that have timeline items of the given types, optionally
+/// only items by a given user or within a time window.
+#[derive(clap::Args, Debug)]
+pub struct Args {
+    /// The types of timeline items to list. Can be given more than once or
+    /// separated by commas.
+    #[arg(
+        long = "event",
+        value_delimiter = ',',
+        default_value = "REOPENED_EVENT",
+        value_parser = clap::builder::PossibleValuesParser::new(TIMELINE_ITEM_TYPES),
+    )]
+    events: Vec<String>,
+
+    /// Only list items by this user, e.g. who added a label or reopened the
+    /// issue.
+    #[arg(long)]
+    actor: Option<String>,
+
+    /// Only list items on or after this date.
+    #[arg(long)]
+    events_since: Option<NaiveDate>,
+
+    /// Only list items before this date.
+    #[arg(long)]
+    events_until: Option<NaiveDate>,
+
+    /// Only list issues that currently have this label.
+    #[arg(long)]
+    label: Option<String>,
+
+    /// Only list issues without comments for longer than this.
+    #[arg(long)]
+    last_comment_months_considered_old: Option<u32>,
+}
+
+#[derive(serde::Serialize)]
+struct EventListing<'a> {
+    url: &'a str,
+    title: &'a str,
+    timeline_items: Vec<&'a TimelineItem>,
+}
+
+pub async fn run(global: &GlobalArgs, args: Args) -> anyhow::Result<()> {
+    let source = global.issue_source()?;
+
+    let output = RefCell::new(Output::stdout(global.format));
+
+    let as_of = global.as_of();
+    source
+        .for_issues_with_timeline(
+            &issues_query(global, &args)?,
+            |issue| {
+                if let Some(months) = args.last_comment_months_considered_old {
+                    if !last_comment_older_than(issue, months, as_of) {
+                        return;
+                    }
+                }
+
+                let timeline_items: Vec<_> = issue
+                    .timeline_items
+                    .iter()
+                    .filter(|item| args.matches(item))
+                    .collect();
+                if !timeline_items.is_empty() {
+                    let listing = EventListing {
+                        url: &issue.url,
+                        title: &issue.title,
+                        timeline_items,
+                    };
+                    output.borrow_mut().print(&listing).unwrap();
+                }
+            },
+            || {},
+        )
+        .await?;
+
+    Ok(())
+}
+
+/// The issues that `run` goes through.
+pub fn issues_query(global: &GlobalArgs, args: &Args) -> anyhow::Result<IssuesQuery> {
+    let mut timeline_item_types = args.events.clone();
+    if args.last_comment_months_considered_old.is_some()
+        && !timeline_item_types.iter().any(|t| t == "ISSUE_COMMENT")
+    {
+        timeline_item_types.push("ISSUE_COMMENT".to_owned());
+    }
+    let mut variables = serde_json::json!({
+        "states": ["OPEN"],
+        "timelineItemTypes": timeline_item_types,
+    });
+    if let Some(label) = &args.label {
+        variables["filterBy"] = serde_json::json!({ "labels": [global.label(label)] });
+    }
+    global.issues_query(variables)
+}
+
+impl Args {
+    fn matches(&self, item: &TimelineItem) -> bool {
+        let day = item.created_at().date_naive();
+        self.events.iter().any(|event| event == item.item_type())
+            && self.actor.as_ref().is_none_or(|login| {
+                item.actor()
+                    .is_some_and(|actor| actor.login.eq_ignore_ascii_case(login))
+            })
+            && self.events_since.is_none_or(|since| day >= since)
+            && self.events_until.is_none_or(|until| day < until)
+    }
+}
+
+/// Whether the last comment, or the creation if there are no comments, was
+/// more than `months` calendar months before `as_of`, like in `old-mcve`.
+fn last_comment_older_than(
+    issue: &IssueWithTimelineItems,
+    months: u32,
+    as_of: DateTime<FixedOffset>,
+) -> bool {
+    let last_comment_at = issue
+        .timeline_items
+        .iter()
+        .filter(|item| matches!(item, TimelineItem::IssueComment { .. }))
+        .map(TimelineItem::created_at)
+        .max()
+        .unwrap_or(issue.created_at);
+    months_between(last_comment_at, as_of) > i64::from(months)
+}
+
+impl Display for EventListing<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.url, self.title)?;
+        for item in &self.timeline_items {
+            write!(f, "\n    {item}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use chrono::DateTime;
+
+    use super::*;
+
+    /// The schema of the machine-readable output. Changing this breaks users.
+    #[test]
+    fn test_event_listing_output_schema() {
+        let timeline_items = [TimelineItem::ClosedEvent {
+            created_at: DateTime::from_str("2020-01-01T00:00:00Z").unwrap(),
+            actor: None,
+        }];
+        let listing = EventListing {
+            url: "https://github.com/rust-lang/rust/issues/1",
+            title: "Title",
+            timeline_items: timeline_items.iter().collect(),
+        };
+
+        let mut json = Output::new(OutputFormat::Json, vec![]);
+        json.print(&listing).unwrap();
+        assert_eq!(
+            String::from_utf8(json.into_inner()).unwrap(),
+            r#"{"url":"https://github.com/rust-lang/rust/issues/1","title":"Title","timeline_items":[{"__typename":"ClosedEvent","createdAt":"2020-01-01T00:00:00Z"}]}
+"#
+        );
+
+        let mut csv = Output::new(OutputFormat::Csv, vec![]);
+        csv.print(&listing).unwrap();
+        assert_eq!(
+            String::from_utf8(csv.into_inner()).unwrap(),
+            "url,title,timeline_items\n\
+             https://github.com/rust-lang/rust/issues/1,Title,\"[{\"\"__typename\"\":\"\"ClosedEvent\"\",\"\"createdAt\"\":\"\"2020-01-01T00:00:00Z\"\"}]\"\n"
+        );
+
+        assert_eq!(
+            listing.to_string(),
+            "https://github.com/rust-lang/rust/issues/1 Title\n    <CLOSED> 2020-01-01"
+        );
+    }
+
+    fn actor(login: &str) -> Option<Actor> {
+        Some(Actor {
+            login: login.to_string(),
+            typename: None,
+        })
+    }
+
+    #[test]
+    fn test_filters() {
+        let args = Args {
+            events: vec!["LABELED_EVENT".to_string(), "CLOSED_EVENT".to_string()],
+            actor: Some("Someone".to_string()),
+            events_since: Some(NaiveDate::from_ymd_opt(2020, 1, 1).unwrap()),
+            events_until: Some(NaiveDate::from_ymd_opt(2021, 1, 1).unwrap()),
+            label: None,
+            last_comment_months_considered_old: Some(12),
+        };
+        let labeled = |at: &str, login| TimelineItem::LabeledEvent {
+            created_at: DateTime::from_str(at).unwrap(),
+            label: Label {
+                name: E_NEEDS_MCVE.to_string(),
+            },
+            actor: actor(login),
+        };
+
+        assert!(args.matches(&labeled("2020-06-01T00:00:00Z", "someone")));
+        assert!(!args.matches(&labeled("2020-06-01T00:00:00Z", "other")));
+        assert!(!args.matches(&labeled("2021-01-01T00:00:00Z", "someone")));
+        assert!(!args.matches(&TimelineItem::IssueComment {
+            created_at: DateTime::from_str("2020-06-01T00:00:00Z").unwrap(),
+            author: actor("someone"),
+            author_association: None,
+        }));
+
+        let issue = IssueWithTimelineItems {
+            url: "N/A".to_string(),
+            number: 1,
+            title: "Title".to_string(),
+            author: None,
+            labels: Labels::new(vec![]),
+            created_at: DateTime::from_str("2019-01-01T00:00:00Z").unwrap(),
+            timeline_items: vec![TimelineItem::IssueComment {
+                created_at: DateTime::from_str("2020-02-29T00:00:00Z").unwrap(),
+                author: actor("someone"),
+                author_association: None,
+            }],
+        };
+        let as_of = DateTime::from_str("2021-03-28T00:00:00Z").unwrap();
+        assert!(!last_comment_older_than(&issue, 12, as_of));
+        let as_of = DateTime::from_str("2021-03-29T00:00:00Z").unwrap();
+        assert!(last_comment_older_than(&issue, 12, as_of));
+    }
+
+    /// `--last-comment-months-considered-old` means the same as for `old-mcve`.
+    #[test]
+    fn test_last_comment_boundary_like_old_mcve() {
+        let at = |s| parse_timestamp(s).unwrap();
+        let issue = IssueWithTimelineItems {
+            url: "N/A".to_string(),
+            number: 1,
+            title: "Title".to_string(),
+            author: None,
+            labels: Labels::new(vec![]),
+            created_at: at("2019-01-01"),
+            timeline_items: vec![
+                TimelineItem::LabeledEvent {
+                    created_at: at("2019-01-01"),
+                    label: Label {
+                        name: E_NEEDS_MCVE.to_string(),
+                    },
+                    actor: None,
+                },
+                TimelineItem::IssueComment {
+                    created_at: at("2020-01-31"),
+                    author: actor("someone"),
+                    author_association: None,
+                },
+            ],
+        };
+
+        for as_of in ["2021-01-30", "2021-01-31", "2021-02-28", "2021-03-01"] {
+            let as_of = at(as_of);
+            let (_, comment_age_months) =
+                crate::commands::old_mcve::get_ages(&issue, as_of).unwrap();
+            assert_eq!(
+                last_comment_older_than(&issue, 12, as_of),
+                comment_age_months > 12,
+                "{as_of}"
+            );
+        }
+        assert!(!last_comment_older_than(&issue, 12, at("2021-02-27")));
+        assert!(last_comment_older_than(&issue, 12, at("2021-02-28")));
+    }
+}
@@ -0,0 +1,87 @@
+//! The `rust-issue-stats.toml` configuration file. Values in it are used for
+//! options that are not given on the command line. Example:
+//!
+//! ```toml
+//! [defaults]
+//! repo = "rust"
+//! page-size = 50
+//!
+//! [repositories]
+//! rust = "rust-lang/rust"
+//! cargo = "rust-lang/cargo"
+//!
+//! [label-aliases]
+//! mcve = "E-needs-mcve"
+//!
+//! [presets.stale-mcve]
+//! command = "old-mcve"
+//! label-months-considered-old = 24
+//! last-comment-months-considered-old = 12
+//! ```
+//!
+//! With that, `rust-issue-stats stale-mcve --pages 5` is the same as
+//! `rust-issue-stats --repo rust-lang/rust --page-size 50 old-mcve
+//! --label-months-considered-old 24 --last-comment-months-considered-old 12
+//! --pages 5`.
+
+use anyhow::Context;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+pub const DEFAULT_CONFIG_PATH: &str = "rust-issue-stats.toml";
+
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Config {
+    /// Values for global options such as `repo` and `page-size`.
+    pub defaults: BTreeMap<String, toml::Value>,
+    /// Short names that can be given to `--repo`, e.g. `cargo =
+    /// "rust-lang/cargo"`.
+    pub repositories: BTreeMap<String, String>,
+    /// Short names that can be given to options that take labels.
+    pub label_aliases: BTreeMap<String, String>,
+    /// Named sets of options for a subcommand. A preset is run like a
+    /// subcommand.
+    pub presets: BTreeMap<String, Preset>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Preset {
+    /// The subcommand to run, e.g. `old-mcve`.
+    pub command: String,
+    /// Values for options of the subcommand or global options, e.g.
+    /// `label-months-considered-old = 24`.
+    #[serde(flatten)]
+    pub options: BTreeMap<String, toml::Value>,
+}
+
+impl Config {
+    pub fn from_file(path: &Path) -> anyhow::Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        toml::from_str(&text).with_context(|| format!("failed to parse {}", path.display()))
+    }
+
+    /// Reads `path`, or [`DEFAULT_CONFIG_PATH`] if it exists. The default
+    /// config is empty.
+    pub fn load(path: Option<&Path>) -> anyhow::Result<Self> {
+        match path {
+            Some(path) => Self::from_file(path),
+            None if Path::new(DEFAULT_CONFIG_PATH).exists() => {
+                Self::from_file(Path::new(DEFAULT_CONFIG_PATH))
+            }
+            None => Ok(Self::default()),
+        }
+    }
+
+    /// Resolves a repository short name. Other values are returned as is.
+    pub fn repository<'a>(&'a self, repo: &'a str) -> &'a str {
+        self.repositories.get(repo).map_or(repo, String::as_str)
+    }
+
+    /// Resolves a label alias. Other labels are returned as is.
+    pub fn label<'a>(&'a self, label: &'a str) -> &'a str {
+        self.label_aliases.get(label).map_or(label, String::as_str)
+    }
+}
@@ -0,0 +1,226 @@
+//! Checks that paging through issues saw each issue once, in order, and none
+//! went missing, which cursors do not guarantee while issues keep changing.
+
+use chrono::{DateTime, FixedOffset};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::fmt::Display;
+use tracing::warn;
+
+use crate::SkippedIssue;
+
+/// What went wrong while paging in
+/// [`GitHub::for_issues_with_timeline`](crate::GitHub::for_issues_with_timeline).
+/// Nothing, usually.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
+pub struct PagingReport {
+    pub skipped: Vec<SkippedIssue>,
+    /// Issues that were on more than one page. They are only handled once.
+    pub duplicates: Vec<u32>,
+    /// Issues created after an issue on an earlier page, although pages go
+    /// from the newest to the oldest issue. They are handled, but the issues
+    /// around them may be missing.
+    pub out_of_order: Vec<u32>,
+    pub gaps: Vec<PagingGap>,
+}
+
+/// Issues that were probably missed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "gap", rename_all = "camelCase")]
+pub enum PagingGap {
+    /// GitHub said there are older issues, but gave no cursor to get them.
+    /// Paging stopped after `oldest_issue`.
+    MissingCursor { oldest_issue: Option<u32> },
+    /// Paging reached the oldest issue, but saw fewer issues than the
+    /// `totalCount` of the first page. Issues that were closed or deleted
+    /// meanwhile also count as unseen.
+    Unseen { total_count: u64, seen: u64 },
+}
+
+/// Keeps track of the issues on the pages so far.
+#[derive(Debug, Default)]
+pub(crate) struct PagingCheck {
+    seen: HashSet<u32>,
+    /// Skipped issues without a number.
+    unknown_skipped: u64,
+    first_total_count: Option<u64>,
+    oldest_on_previous_pages: Option<DateTime<FixedOffset>>,
+    oldest_on_page: Option<(u32, DateTime<FixedOffset>)>,
+    report: PagingReport,
+}
+
+impl PagingCheck {
+    pub(crate) fn start_page(&mut self, total_count: Option<u64>) {
+        if self.first_total_count.is_none() && self.seen.is_empty() {
+            self.first_total_count = total_count;
+        }
+        if let Some((_, created_at)) = self.oldest_on_page.take() {
+            self.oldest_on_previous_pages = Some(created_at);
+        }
+    }
+
+    /// Returns `false` for issues that were already seen.
+    pub(crate) fn check_issue(&mut self, number: u32, created_at: DateTime<FixedOffset>) -> bool {
+        if !self.seen.insert(number) {
+            warn!("issue {number} was on an earlier page already, ignoring it");
+            self.report.duplicates.push(number);
+            return false;
+        }
+        if self
+            .oldest_on_previous_pages
+            .is_some_and(|oldest| created_at > oldest)
+        {
+            warn!("issue {number} was created after issues on earlier pages");
+            self.report.out_of_order.push(number);
+        }
+        if self
+            .oldest_on_page
+            .is_none_or(|(_, oldest)| created_at < oldest)
+        {
+            self.oldest_on_page = Some((number, created_at));
+        }
+        true
+    }
+
+    pub(crate) fn skip(&mut self, issue: SkippedIssue) {
+        match issue.number {
+            Some(number) => {
+                self.seen.insert(number);
+            }
+            None => self.unknown_skipped += 1,
+        }
+        self.report.skipped.push(issue);
+    }
+
+    /// There are older issues, but no cursor to get them.
+    pub(crate) fn missing_cursor(&mut self) {
+        let oldest_issue = self.oldest_on_page.map(|(number, _)| number);
+        warn!("no cursor for the page before issue {oldest_issue:?}, stopping");
+        self.report
+            .gaps
+            .push(PagingGap::MissingCursor { oldest_issue });
+    }
+
+    /// Paging reached the oldest issue, so all issues should have been seen.
+    pub(crate) fn reached_oldest_issue(&mut self) {
+        let seen = self.seen.len() as u64 + self.unknown_skipped;
+        if let Some(total_count) = self.first_total_count.filter(|total| seen < *total) {
+            warn!("saw {seen} of {total_count} issues");
+            self.report
+                .gaps
+                .push(PagingGap::Unseen { total_count, seen });
+        }
+    }
+
+    pub(crate) fn finish(self) -> PagingReport {
+        self.report
+    }
+}
+
+impl PagingReport {
+    pub fn is_clean(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+fn numbers(numbers: &[u32]) -> String {
+    let numbers: Vec<_> = numbers.iter().map(|number| format!("#{number}")).collect();
+    numbers.join(", ")
+}
+
+impl Display for PagingReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if !self.skipped.is_empty() {
+            writeln!(
+                f,
+                "Skipped {} issues that GitHub returned errors for:",
+                self.skipped.len()
+            )?;
+            for issue in &self.skipped {
+                writeln!(f, "  {issue}")?;
+            }
+        }
+        if !self.duplicates.is_empty() {
+            writeln!(
+                f,
+                "Ignored issues that were on more than one page: {}",
+                numbers(&self.duplicates)
+            )?;
+        }
+        if !self.out_of_order.is_empty() {
+            writeln!(
+                f,
+                "Issues that were out of order, so that issues may be missing around them: {}",
+                numbers(&self.out_of_order)
+            )?;
+        }
+        for gap in &self.gaps {
+            match gap {
+                PagingGap::MissingCursor {
+                    oldest_issue: Some(number),
+                } => writeln!(f, "Stopped before the oldest issue after #{number}")?,
+                PagingGap::MissingCursor { oldest_issue: None } => {
+                    writeln!(f, "Stopped before the oldest issue")?
+                }
+                PagingGap::Unseen { total_count, seen } => writeln!(
+                    f,
+                    "Saw {seen} of {total_count} issues, some were closed, deleted or missed"
+                )?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(day: u32) -> DateTime<FixedOffset> {
+        format!("2023-01-{day:02}T00:00:00Z").parse().unwrap()
+    }
+
+    #[test]
+    fn test_paging_check() {
+        let mut check = PagingCheck::default();
+        check.start_page(Some(6));
+        assert!(check.check_issue(4, at(4)));
+        assert!(check.check_issue(5, at(5)));
+
+        // Issue 4 again because a new issue shifted the cursor, and issue 6
+        // that should have been on the first page.
+        check.start_page(Some(7));
+        assert!(check.check_issue(3, at(3)));
+        assert!(!check.check_issue(4, at(4)));
+        assert!(check.check_issue(6, at(6)));
+
+        check.start_page(Some(7));
+        check.skip(SkippedIssue {
+            number: None,
+            errors: vec!["forbidden".to_owned()],
+        });
+        check.reached_oldest_issue();
+
+        let report = check.finish();
+        assert_eq!(report.duplicates, [4]);
+        assert_eq!(report.out_of_order, [6]);
+        assert_eq!(
+            report.gaps,
+            [PagingGap::Unseen {
+                total_count: 6,
+                seen: 5
+            }]
+        );
+        assert!(!report.is_clean());
+        assert_eq!(
+            report.to_string(),
+            "\
+Skipped 1 issues that GitHub returned errors for:
+  an unknown issue: forbidden
+Ignored issues that were on more than one page: #4
+Issues that were out of order, so that issues may be missing around them: #6
+Saw 5 of 6 issues, some were closed, deleted or missed
+"
+        );
+    }
+}
@@ -0,0 +1,525 @@
+//! Reconstruction of the state of an issue at any point in time from its
+//! timeline. This only works if the timeline was queried with the relevant
+//! item types: `LABELED_EVENT` and `UNLABELED_EVENT` for labels, and
+//! `CLOSED_EVENT` and `REOPENED_EVENT` for open/closed state.
+
+use chrono::{DateTime, FixedOffset};
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+
+use crate::{IssueWithTimelineItems, Label, Labels, TimelineItem};
+
+/// A period of time. `end` is `None` if the period has not ended yet.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Interval {
+    pub start: DateTime<FixedOffset>,
+    pub end: Option<DateTime<FixedOffset>>,
+}
+
+/// Label intervals per label name, together with the inconsistencies found
+/// while reconstructing them.
+#[derive(Debug, Default)]
+pub struct LabelHistory {
+    pub intervals: BTreeMap<String, Vec<Interval>>,
+    pub inconsistencies: Vec<LabelInconsistency>,
+}
+
+/// Issues as they were at a point in time, e.g. to see what the open
+/// `E-needs-mcve` backlog looked like on a given date.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Snapshot {
+    pub as_of: DateTime<FixedOffset>,
+    /// Only issues that had been created at `as_of`, as returned by
+    /// [`IssueWithTimelineItems::at`].
+    pub issues: Vec<IssueWithTimelineItems>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum LabelInconsistency {
+    /// The label was removed without having been added. It is assumed to have
+    /// been there since the issue was created.
+    UnlabeledWithoutLabel {
+        label: String,
+        at: DateTime<FixedOffset>,
+    },
+
+    /// The label was added even though the issue already had it.
+    LabeledTwice {
+        label: String,
+        at: DateTime<FixedOffset>,
+    },
+
+    /// The issue has the label now, but the timeline does not say it was ever
+    /// added. It is assumed to have been there since the issue was created or
+    /// since the label was last removed.
+    MissingLabeledEvent { label: String },
+
+    /// According to the timeline the issue has the label, but it does not have
+    /// it any more. This happens if the label was deleted or renamed.
+    MissingUnlabeledEvent { label: String },
+}
+
+impl Interval {
+    pub fn contains(&self, timestamp: DateTime<FixedOffset>) -> bool {
+        self.start <= timestamp && self.end.is_none_or(|end| timestamp < end)
+    }
+}
+
+/// Merges overlapping and adjacent intervals. The result is sorted.
+pub fn union(mut intervals: Vec<Interval>) -> Vec<Interval> {
+    intervals.sort_by_key(|interval| interval.start);
+
+    let mut merged: Vec<Interval> = vec![];
+    for interval in intervals {
+        match merged.last_mut() {
+            Some(last) if last.end.is_none_or(|end| interval.start <= end) => {
+                last.end = match (last.end, interval.end) {
+                    (Some(a), Some(b)) => Some(a.max(b)),
+                    _ => None,
+                };
+            }
+            _ => merged.push(interval),
+        }
+    }
+    merged
+}
+
+/// The periods covered by both `a` and `b`, which must be sorted and free from
+/// overlaps, like the result of [`union`].
+pub fn intersection(a: &[Interval], b: &[Interval]) -> Vec<Interval> {
+    let mut result = vec![];
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        let start = a[i].start.max(b[j].start);
+        let end = match (a[i].end, b[j].end) {
+            (Some(x), Some(y)) => Some(x.min(y)),
+            (x, None) => x,
+            (None, y) => y,
+        };
+        if end.is_none_or(|end| start < end) {
+            result.push(Interval { start, end });
+        }
+
+        // Advance whichever interval ends first.
+        match (a[i].end, b[j].end) {
+            (Some(x), Some(y)) if x < y => i += 1,
+            (Some(_), None) => i += 1,
+            _ => j += 1,
+        }
+    }
+    result
+}
+
+impl IssueWithTimelineItems {
+    /// Timeline items in chronological order. GitHub already returns them in
+    /// that order, but we do not want to rely on it.
+    pub(crate) fn chronological_timeline_items(&self) -> Vec<&TimelineItem> {
+        let mut items: Vec<_> = self.timeline_items.iter().collect();
+        items.sort_by_key(|item| item.created_at());
+        items
+    }
+
+    /// Replays label events and reconciles the result with the current labels.
+    pub fn label_history(&self) -> LabelHistory {
+        let mut history = LabelHistory::default();
+
+        for item in self.chronological_timeline_items() {
+            match item {
+                TimelineItem::LabeledEvent {
+                    label, created_at, ..
+                } => {
+                    let intervals = history.intervals.entry(label.name.clone()).or_default();
+                    if intervals.last().is_some_and(|last| last.end.is_none()) {
+                        history
+                            .inconsistencies
+                            .push(LabelInconsistency::LabeledTwice {
+                                label: label.name.clone(),
+                                at: *created_at,
+                            });
+                    } else {
+                        intervals.push(Interval {
+                            start: *created_at,
+                            end: None,
+                        });
+                    }
+                }
+                TimelineItem::UnlabeledEvent {
+                    label, created_at, ..
+                } => {
+                    let intervals = history.intervals.entry(label.name.clone()).or_default();
+                    match intervals.last_mut() {
+                        Some(last) if last.end.is_none() => last.end = Some(*created_at),
+                        last => {
+                            history.inconsistencies.push(
+                                LabelInconsistency::UnlabeledWithoutLabel {
+                                    label: label.name.clone(),
+                                    at: *created_at,
+                                },
+                            );
+                            if last.is_none() {
+                                intervals.push(Interval {
+                                    start: self.created_at,
+                                    end: Some(*created_at),
+                                });
+                            }
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        for label in &self.labels.nodes {
+            let intervals = history.intervals.entry(label.name.clone()).or_default();
+            match intervals.last() {
+                Some(last) if last.end.is_none() => {}
+                last => {
+                    history
+                        .inconsistencies
+                        .push(LabelInconsistency::MissingLabeledEvent {
+                            label: label.name.clone(),
+                        });
+                    intervals.push(Interval {
+                        start: last.and_then(|last| last.end).unwrap_or(self.created_at),
+                        end: None,
+                    });
+                }
+            }
+        }
+
+        for (name, intervals) in &history.intervals {
+            let still_labeled = intervals.last().is_some_and(|last| last.end.is_none());
+            if still_labeled && !self.labels.nodes.iter().any(|label| &label.name == name) {
+                history
+                    .inconsistencies
+                    .push(LabelInconsistency::MissingUnlabeledEvent {
+                        label: name.clone(),
+                    });
+            }
+        }
+
+        history
+    }
+
+    /// The labels the issue had at the given point in time, sorted by name.
+    pub fn labels_at(&self, timestamp: DateTime<FixedOffset>) -> Vec<Label> {
+        self.label_history()
+            .intervals
+            .into_iter()
+            .filter(|(_, intervals)| intervals.iter().any(|i| i.contains(timestamp)))
+            .map(|(name, _)| Label { name })
+            .collect()
+    }
+
+    /// The periods during which the issue had the given label.
+    pub fn label_intervals(&self, name: &str) -> Vec<Interval> {
+        self.label_history()
+            .intervals
+            .remove(name)
+            .unwrap_or_default()
+    }
+
+    /// The periods during which the issue was open. Closing a closed issue and
+    /// reopening an open issue is ignored.
+    pub fn open_intervals(&self) -> Vec<Interval> {
+        let mut intervals = vec![Interval {
+            start: self.created_at,
+            end: None,
+        }];
+
+        for item in self.chronological_timeline_items() {
+            let last = intervals.last_mut().expect("starts with one interval");
+            match item {
+                TimelineItem::ClosedEvent { created_at, .. } if last.end.is_none() => {
+                    last.end = Some(*created_at);
+                }
+                TimelineItem::ReopenedEvent { created_at, .. } if last.end.is_some() => {
+                    intervals.push(Interval {
+                        start: *created_at,
+                        end: None,
+                    });
+                }
+                _ => {}
+            }
+        }
+
+        intervals
+    }
+
+    pub fn was_open_at(&self, timestamp: DateTime<FixedOffset>) -> bool {
+        self.open_intervals()
+            .iter()
+            .any(|interval| interval.contains(timestamp))
+    }
+
+    /// The issue as it was at the given point in time: with the labels it had
+    /// then and without later timeline items. `None` if it had not been
+    /// created yet.
+    pub fn at(&self, timestamp: DateTime<FixedOffset>) -> Option<IssueWithTimelineItems> {
+        if self.created_at > timestamp {
+            return None;
+        }
+
+        Some(IssueWithTimelineItems {
+            labels: Labels {
+                nodes: self.labels_at(timestamp),
+                complete: self.labels.complete,
+            },
+            timeline_items: self
+                .timeline_items
+                .iter()
+                .filter(|item| item.created_at() <= timestamp)
+                .cloned()
+                .collect(),
+            ..self.clone()
+        })
+    }
+}
+
+impl Snapshot {
+    pub fn new<'a>(
+        issues: impl IntoIterator<Item = &'a IssueWithTimelineItems>,
+        as_of: DateTime<FixedOffset>,
+    ) -> Self {
+        Self {
+            as_of,
+            issues: issues
+                .into_iter()
+                .filter_map(|issue| issue.at(as_of))
+                .collect(),
+        }
+    }
+
+    /// The issues that were open at `as_of`.
+    pub fn open_issues(&self) -> impl Iterator<Item = &IssueWithTimelineItems> {
+        self.issues
+            .iter()
+            .filter(|issue| issue.was_open_at(self.as_of))
+    }
+
+    /// The issues that were open and had the given label at `as_of`.
+    pub fn open_issues_with_label<'a>(
+        &'a self,
+        name: &'a str,
+    ) -> impl Iterator<Item = &'a IssueWithTimelineItems> {
+        self.open_issues()
+            .filter(move |issue| issue.labels.nodes.iter().any(|label| label.name == name))
+    }
+}
+
+impl Display for LabelInconsistency {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LabelInconsistency::UnlabeledWithoutLabel { label, at } => write!(
+                f,
+                "-{label} {} without being labeled",
+                at.format("%Y-%m-%d")
+            ),
+            LabelInconsistency::LabeledTwice { label, at } => {
+                write!(
+                    f,
+                    "+{label} {} while already labeled",
+                    at.format("%Y-%m-%d")
+                )
+            }
+            LabelInconsistency::MissingLabeledEvent { label } => {
+                write!(f, "{label} present but never labeled")
+            }
+            LabelInconsistency::MissingUnlabeledEvent { label } => {
+                write!(f, "{label} labeled but no longer present")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{date, issue, label};
+
+    #[test]
+    fn test_labels_at() {
+        let issue = issue(
+            &["C-bug", "T-compiler"],
+            vec![
+                TimelineItem::LabeledEvent {
+                    label: label("C-bug"),
+                    created_at: date("2020-01-02T00:00:00Z"),
+                    actor: None,
+                },
+                TimelineItem::LabeledEvent {
+                    label: label("E-needs-mcve"),
+                    created_at: date("2020-01-03T00:00:00Z"),
+                    actor: None,
+                },
+                TimelineItem::UnlabeledEvent {
+                    label: label("E-needs-mcve"),
+                    created_at: date("2020-01-05T00:00:00Z"),
+                    actor: None,
+                },
+            ],
+        );
+
+        assert_eq!(
+            issue.labels_at(date("2020-01-04T00:00:00Z")),
+            vec![label("C-bug"), label("E-needs-mcve"), label("T-compiler")]
+        );
+        assert_eq!(
+            issue.labels_at(date("2020-01-05T00:00:00Z")),
+            vec![label("C-bug"), label("T-compiler")]
+        );
+        assert_eq!(
+            issue.label_intervals("E-needs-mcve"),
+            vec![Interval {
+                start: date("2020-01-03T00:00:00Z"),
+                end: Some(date("2020-01-05T00:00:00Z")),
+            }]
+        );
+        assert_eq!(
+            issue.label_history().inconsistencies,
+            vec![LabelInconsistency::MissingLabeledEvent {
+                label: "T-compiler".to_string()
+            }]
+        );
+    }
+
+    #[test]
+    fn test_label_inconsistencies() {
+        let issue = issue(
+            &[],
+            vec![
+                TimelineItem::UnlabeledEvent {
+                    label: label("A-old"),
+                    created_at: date("2020-01-02T00:00:00Z"),
+                    actor: None,
+                },
+                TimelineItem::LabeledEvent {
+                    label: label("A-renamed"),
+                    created_at: date("2020-01-03T00:00:00Z"),
+                    actor: None,
+                },
+            ],
+        );
+
+        assert_eq!(
+            issue.label_history().inconsistencies,
+            vec![
+                LabelInconsistency::UnlabeledWithoutLabel {
+                    label: "A-old".to_string(),
+                    at: date("2020-01-02T00:00:00Z"),
+                },
+                LabelInconsistency::MissingUnlabeledEvent {
+                    label: "A-renamed".to_string()
+                },
+            ]
+        );
+        assert!(issue
+            .labels_at(date("2020-01-01T12:00:00Z"))
+            .contains(&label("A-old")));
+    }
+
+    #[test]
+    fn test_union_and_intersection() {
+        let interval = |start: &str, end: Option<&str>| Interval {
+            start: date(start),
+            end: end.map(date),
+        };
+
+        let a = union(vec![
+            interval("2020-01-05T00:00:00Z", None),
+            interval("2020-01-01T00:00:00Z", Some("2020-01-03T00:00:00Z")),
+            interval("2020-01-02T00:00:00Z", Some("2020-01-04T00:00:00Z")),
+        ]);
+        assert_eq!(
+            a,
+            vec![
+                interval("2020-01-01T00:00:00Z", Some("2020-01-04T00:00:00Z")),
+                interval("2020-01-05T00:00:00Z", None),
+            ]
+        );
+
+        let b = vec![interval(
+            "2020-01-03T00:00:00Z",
+            Some("2020-01-06T00:00:00Z"),
+        )];
+        assert_eq!(
+            intersection(&a, &b),
+            vec![
+                interval("2020-01-03T00:00:00Z", Some("2020-01-04T00:00:00Z")),
+                interval("2020-01-05T00:00:00Z", Some("2020-01-06T00:00:00Z")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_was_open_at() {
+        let issue = issue(
+            &[],
+            vec![
+                TimelineItem::ClosedEvent {
+                    created_at: date("2020-01-02T00:00:00Z"),
+                    actor: None,
+                },
+                TimelineItem::ClosedEvent {
+                    created_at: date("2020-01-03T00:00:00Z"),
+                    actor: None,
+                },
+                TimelineItem::ReopenedEvent {
+                    created_at: date("2020-01-04T00:00:00Z"),
+                    actor: crate::Actor {
+                        login: "someone".to_string(),
+                        typename: None,
+                    },
+                },
+            ],
+        );
+
+        assert!(!issue.was_open_at(date("2019-12-31T00:00:00Z")));
+        assert!(issue.was_open_at(date("2020-01-01T12:00:00Z")));
+        assert!(!issue.was_open_at(date("2020-01-03T12:00:00Z")));
+        assert!(issue.was_open_at(date("2020-01-05T00:00:00Z")));
+    }
+
+    #[test]
+    fn test_snapshot() {
+        let labeled_then_closed = issue(
+            &[],
+            vec![
+                TimelineItem::LabeledEvent {
+                    label: label("E-needs-mcve"),
+                    created_at: date("2020-01-02T00:00:00Z"),
+                    actor: None,
+                },
+                TimelineItem::ClosedEvent {
+                    created_at: date("2020-03-01T00:00:00Z"),
+                    actor: None,
+                },
+                TimelineItem::UnlabeledEvent {
+                    label: label("E-needs-mcve"),
+                    created_at: date("2020-03-01T00:00:00Z"),
+                    actor: None,
+                },
+            ],
+        );
+        let created_later = IssueWithTimelineItems {
+            created_at: date("2020-06-01T00:00:00Z"),
+            ..issue(&["E-needs-mcve"], vec![])
+        };
+        let issues = [labeled_then_closed, created_later];
+
+        let snapshot = Snapshot::new(&issues, date("2020-02-01T00:00:00Z"));
+        assert_eq!(snapshot.issues.len(), 1);
+        let open: Vec<_> = snapshot.open_issues_with_label("E-needs-mcve").collect();
+        assert_eq!(open.len(), 1);
+        assert_eq!(open[0].labels.nodes, vec![label("E-needs-mcve")]);
+        assert_eq!(open[0].timeline_items.len(), 1);
+
+        let snapshot = Snapshot::new(&issues, date("2020-07-01T00:00:00Z"));
+        assert_eq!(snapshot.issues.len(), 2);
+        assert_eq!(snapshot.open_issues_with_label("E-needs-mcve").count(), 1);
+        assert_eq!(
+            snapshot.open_issues().next().unwrap().created_at,
+            date("2020-06-01T00:00:00Z")
+        );
+    }
+}
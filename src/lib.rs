@@ -1,28 +1,113 @@
+use anyhow::Context;
+use chrono::{DateTime, FixedOffset, Utc};
+use consistency::PagingCheck;
+use page_size::{is_too_heavy, AdaptivePageSize, DEFAULT_TIMELINE_PAGE_SIZE};
+use std::path::PathBuf;
+use std::time::Duration;
 use tracing::{subscriber::SetGlobalDefaultError, *};
 
+mod bulk;
+mod calendar;
+pub mod cli;
+mod client;
+mod commands;
+mod config;
+mod consistency;
+mod history;
 mod models;
+mod output;
+mod page_size;
+mod plan;
+mod progress;
 mod queries;
+mod report;
+mod source;
+mod stats;
+#[cfg(test)]
+mod test_support;
+mod validate;
 
+pub use bulk::*;
+pub use calendar::*;
+pub use client::*;
+pub use config::*;
+pub use consistency::*;
+pub use history::*;
 pub use models::*;
+pub use output::*;
+pub use plan::*;
+pub use progress::*;
+pub use report::*;
+pub use source::*;
+pub use stats::*;
 use tracing_subscriber::FmtSubscriber;
+pub use validate::*;
 
 /// GitHub GraphQL API wrapper.
 pub struct GitHub {
     octocrab: octocrab::Octocrab,
+    cache_dir: Option<PathBuf>,
+    timeout: Option<Duration>,
+    budget: Budget,
+    progress: Option<Box<dyn ProgressHook + Send + Sync>>,
 }
 
-impl Default for GitHub {
-    fn default() -> Self {
-        Self::new()
-    }
+/// Which issues [`GitHub::for_issues_with_timeline`] goes through. Issues are
+/// paged from the most recently created to the oldest.
+#[derive(Debug, Clone)]
+pub struct IssuesQuery {
+    /// Variables for the `ISSUES_WITH_TIMELINE_QUERY`, such as
+    /// `page_size`, `timelineItemTypes`, `owner` and `name`.
+    pub variables: serde_json::Value,
+    /// The maximum number of pages to fetch.
+    pub pages: usize,
+    /// Issues created before this are skipped, and no more pages are fetched
+    /// once one is seen.
+    pub created_since: Option<DateTime<Utc>>,
+    /// Issues created at or after this are skipped. Pages with only such
+    /// issues do not count towards `pages`.
+    pub created_until: Option<DateTime<Utc>>,
+    /// Skip issues with [`Anomaly`]s in their timeline, as far as they can be
+    /// told from the queried `timelineItemTypes`.
+    pub exclude_anomalous: bool,
+    /// When the `states` of issues in data dumps are determined, e.g.
+    /// `--as-of`. Defaults to now. GitHub always uses the current state.
+    pub states_at: Option<DateTime<FixedOffset>>,
 }
 
 impl GitHub {
-    pub fn new() -> Self {
-        Self { octocrab: octocrab::Octocrab::builder()
-            .personal_token(std::env::var("GITHUB_TOKEN").expect("go to https://github.com/settings/tokens?type=beta and generate a token that can read public repos"))
-            .build()
-            .unwrap() }
+    /// Configured by the environment, see [`GitHubBuilder`].
+    pub fn new() -> anyhow::Result<Self> {
+        Self::builder().build()
+    }
+
+    pub fn builder() -> GitHubBuilder {
+        GitHubBuilder::default()
+    }
+
+    /// Uses an already configured client, e.g. one with another `base_uri`.
+    pub fn from_octocrab(octocrab: octocrab::Octocrab) -> Self {
+        Self {
+            octocrab,
+            cache_dir: None,
+            timeout: None,
+            budget: Budget::default(),
+            progress: None,
+        }
+    }
+
+    /// Store responses in `cache_dir` and reuse them instead of making the same
+    /// request again. Remove the directory to get fresh data.
+    pub fn with_cache_dir(mut self, cache_dir: PathBuf) -> Self {
+        self.cache_dir = Some(cache_dir);
+        self
+    }
+
+    /// Report the [`Progress`] of [`Self::for_issues_with_timeline`] to `hook`,
+    /// e.g. a [`ProgressDisplay`].
+    pub fn with_progress(mut self, hook: impl ProgressHook + Send + Sync + 'static) -> Self {
+        self.progress = Some(Box::new(hook));
+        self
     }
 }
 
@@ -31,68 +116,337 @@ impl GitHub {
         &self,
         query: &(impl serde::Serialize + ?Sized),
         variables: serde_json::Value,
-    ) -> octocrab::Result<QueryResponse> {
+    ) -> anyhow::Result<QueryResponse> {
         let json = serde_json::json!({
             "query": query,
             "variables": variables,
         });
 
+        let cache_path = self
+            .cache_dir
+            .as_ref()
+            .map(|dir| dir.join(format!("{:016x}.json", fnv1a(json.to_string().as_bytes()))));
+        if let Some(cached) = cache_path
+            .as_ref()
+            .and_then(|path| std::fs::read(path).ok())
+        {
+            match serde_json::from_slice(&cached) {
+                Ok(response) => {
+                    trace!("Cached query: {}", &json);
+                    return Ok(response);
+                }
+                Err(e) => warn!("ignoring corrupt cache entry {cache_path:?}: {e}"),
+            }
+        }
+
         warn!("making a GitHub API request (affecting rate limiting)");
         trace!("Query: {}", &json);
-        self.octocrab.graphql(&json).await
+        let response = self.graphql(&json).await?;
+
+        if let (Some(path), None) = (&cache_path, &response.errors) {
+            let written = std::fs::create_dir_all(path.parent().expect("has a directory"))
+                .and_then(|()| std::fs::write(path, serde_json::to_vec(&response)?));
+            if let Err(e) = written {
+                warn!("failed to write cache entry {path:?}: {e}");
+            }
+        }
+
+        Ok(response)
+    }
+
+    /// Makes a request within the budget, and spends what it cost according to
+    /// `rateLimit { cost }` in the response, or 1 if it was not queried.
+    async fn graphql(&self, json: &serde_json::Value) -> anyhow::Result<QueryResponse> {
+        self.budget.check()?;
+        let request = self.octocrab.graphql(json);
+        let response: QueryResponse = match self.timeout {
+            Some(timeout) => tokio::time::timeout(timeout, request)
+                .await
+                .map_err(|_| RequestTimeout(timeout))??,
+            None => request.await?,
+        };
+        let cost = response
+            .data
+            .as_ref()
+            .and_then(|data| data["rateLimit"]["cost"].as_u64())
+            .unwrap_or(1);
+        self.budget.spend(cost);
+        Ok(response)
+    }
+
+    /// The requests made so far, not counting cached responses.
+    pub fn api_usage(&self) -> ApiUsage {
+        self.budget.used()
+    }
+
+    /// Like [`Self::query`] but never cached, for mutations and for data that
+    /// must be current. GraphQL errors are returned as errors.
+    async fn query_uncached(
+        &self,
+        query: &str,
+        variables: serde_json::Value,
+    ) -> anyhow::Result<serde_json::Value> {
+        let json = serde_json::json!({
+            "query": query,
+            "variables": variables,
+        });
+
+        warn!("making an uncached GitHub API request (affecting rate limiting)");
+        trace!("Query: {}", &json);
+        let response = self.graphql(&json).await?;
+        if let Some(errors) = response.errors {
+            anyhow::bail!(
+                "GitHub returned errors: {}",
+                serde_json::Value::from(errors)
+            );
+        }
+        Ok(response.data.unwrap_or_default())
     }
 
+    /// Calls `issue_handler` for each issue that matches `query`, and
+    /// `after_page_handler` after each page. Stops early without an error if
+    /// the budget is exhausted.
+    ///
+    /// Issues that GitHub returns errors for, e.g. because they were deleted or
+    /// transferred meanwhile, are skipped, and issues that are on more than one
+    /// page are handled once. The returned [`PagingReport`] lists them, and is
+    /// printed to stderr unless it is clean.
     pub async fn for_issues_with_timeline(
         &self,
-        mut variables: serde_json::Value,
-        pages: usize,
+        query: &IssuesQuery,
         mut issue_handler: impl FnMut(&IssueWithTimelineItems),
         mut after_page_handler: impl FnMut(),
-    ) {
-        let mut pages_left = pages;
+    ) -> anyhow::Result<PagingReport> {
+        let mut variables = query.variables.clone();
+        let max_page_size = variables["page_size"]
+            .as_u64()
+            .context("no page_size in query")?;
+        // Pages may be smaller than `page_size` after timeouts, so count issues
+        // rather than pages to fetch as many issues as without timeouts.
+        let mut issues_left = query.pages as u64 * max_page_size;
+        let mut page_size = AdaptivePageSize::new(max_page_size);
+        let mut timeline_page_size = AdaptivePageSize::new(
+            variables["timeline_page_size"]
+                .as_u64()
+                .unwrap_or(DEFAULT_TIMELINE_PAGE_SIZE),
+        );
+        let mut check = PagingCheck::default();
+        let progress_hook = ProgressGuard(self.progress.as_deref());
+        let started = std::time::Instant::now();
+        let mut progress = Progress {
+            expected_pages: Some(query.pages as u64),
+            ..Progress::default()
+        };
         loop {
-            let mut issues: Issues = self
-                .query(queries::ISSUES_WITH_TIMELINE_QUERY, variables.clone())
-                .await
-                .unwrap()
-                .get(&["repository", "issues"])
-                .unwrap();
+            let mut page_variables;
+            let mut retried = false;
+            let response = loop {
+                page_variables = variables.clone();
+                page_variables["page_size"] = page_size.get().min(issues_left).into();
+                if timeline_page_size.is_reduced() {
+                    page_variables["timeline_page_size"] = timeline_page_size.get().into();
+                }
+                let response = self
+                    .query(queries::ISSUES_WITH_TIMELINE_QUERY, page_variables.clone())
+                    .await;
+                if !is_too_heavy(&response) {
+                    break response;
+                }
+                // Both shrink, even if one of them is 1 already.
+                if !(page_size.shrink() | timeline_page_size.shrink()) {
+                    break response;
+                }
+                warn!(
+                    "GitHub gave up on a page of issues, retrying with {} issues and {} timeline items per page",
+                    page_size.get(),
+                    timeline_page_size.get(),
+                );
+                retried = true;
+            };
+            let response = match response {
+                Err(e) if e.is::<BudgetExhausted>() => {
+                    progress_hook.clear();
+                    eprintln!("Stopping early, results are partial: {e}");
+                    break;
+                }
+                response => response?,
+            };
+            let (mut issues, skipped_nodes): (Issues, _) =
+                response.get_skipping_nodes(&["repository", "issues"])?;
+            check.start_page(issues.total_count);
+            for (node, errors) in skipped_nodes {
+                progress.issues += 1;
+                let number = node["number"].as_u64().and_then(|n| u32::try_from(n).ok());
+                warn!("skipping issue {number:?}: {errors}");
+                check.skip(SkippedIssue::new(number, &errors));
+            }
 
-            pages_left -= 1;
+            if !retried && (page_size.is_reduced() || timeline_page_size.is_reduced()) {
+                page_size.grow();
+                timeline_page_size.grow();
+                info!(
+                    "Growing pages back to {} issues and {} timeline items",
+                    page_size.get(),
+                    timeline_page_size.get(),
+                );
+            }
+            if let Some(total_count) = issues.total_count {
+                let pages = total_count
+                    .div_ceil(max_page_size)
+                    .clamp(1, query.pages as u64);
+                progress.expected_pages = Some(pages);
+                progress.expected_issues = Some(total_count.min(pages * max_page_size));
+            }
 
+            let mut reached_created_since = false;
+            let mut reached_created_until = false;
+            let mut budget_exhausted = None;
             for paged_issue in &mut issues.nodes {
-                let issue = paged_issue.collect_pages(self).await.unwrap();
+                progress.issues += 1;
+                if !query.is_after_range(paged_issue.created_at) {
+                    reached_created_until = true;
+                }
+                if !check.check_issue(paged_issue.number, paged_issue.created_at) {
+                    continue;
+                }
+                if query.is_before_range(paged_issue.created_at) {
+                    reached_created_since = true;
+                    continue;
+                }
+                if !query.is_in_range(paged_issue.created_at) {
+                    continue;
+                }
+
+                let issue = match paged_issue.collect_pages(self, &page_variables).await {
+                    Err(e) if e.is::<BudgetExhausted>() => {
+                        budget_exhausted = Some(e);
+                        break;
+                    }
+                    Err(e) => match e.downcast::<GraphQLErrors>() {
+                        Ok(errors) if errors.are_about_data() => {
+                            warn!("skipping issue {}: {errors}", paged_issue.number);
+                            check.skip(SkippedIssue::new(Some(paged_issue.number), &errors));
+                            continue;
+                        }
+                        Ok(errors) => return Err(errors.into()),
+                        Err(e) => return Err(e),
+                    },
+                    Ok(issue) => issue,
+                };
+                if query.is_excluded_as_anomalous(&issue) {
+                    debug!("Excluding issue {} because of anomalies", issue.number);
+                    continue;
+                }
 
+                progress_hook.clear();
                 issue_handler(&issue);
+                progress_hook.update(&mut progress, self, started);
+            }
+
+            // Pages of issues that are all too new, e.g. with `--as-of` long
+            // ago, do not count.
+            if reached_created_until {
+                issues_left -= page_variables["page_size"].as_u64().expect("set above");
+            } else {
+                debug!("No issues created before created_until on this page yet");
             }
 
+            progress.pages += 1;
+            progress_hook.clear();
             after_page_handler();
+            progress_hook.update(&mut progress, self, started);
 
-            if pages_left == 0 {
+            if let Some(e) = budget_exhausted {
+                progress_hook.clear();
+                eprintln!("Stopping early, results are partial: {e}");
                 break;
             }
 
             if !issues.page_info.has_previous_page {
-                debug!("No more pages left. Maybe unexpected. Raw data: {issues:#?}");
+                debug!("Reached the oldest issue");
+                check.reached_oldest_issue();
                 break;
             }
 
-            variables.as_object_mut().unwrap().insert(
-                "before".to_owned(),
-                serde_json::json!(issues
-                    .page_info
-                    .start_cursor
-                    .expect("has_previous_page is true")),
-            );
+            if issues_left == 0 || reached_created_since {
+                break;
+            }
+
+            let Some(start_cursor) = issues.page_info.start_cursor else {
+                check.missing_cursor();
+                break;
+            };
+            variables["before"] = start_cursor.into();
+        }
+
+        let report = check.finish();
+        if !report.is_clean() {
+            progress_hook.clear();
+            eprint!("{report}");
         }
+        Ok(report)
     }
 }
 
+impl IssuesQuery {
+    pub fn new(variables: serde_json::Value, pages: usize) -> Self {
+        Self {
+            variables,
+            pages,
+            created_since: None,
+            created_until: None,
+            exclude_anomalous: false,
+            states_at: None,
+        }
+    }
+
+    fn is_before_range(&self, created_at: DateTime<FixedOffset>) -> bool {
+        self.created_since
+            .is_some_and(|created_since| created_at < created_since)
+    }
+
+    fn is_after_range(&self, created_at: DateTime<FixedOffset>) -> bool {
+        self.created_until
+            .is_some_and(|created_until| created_at >= created_until)
+    }
+
+    /// Whether an issue created at `created_at` should be handled.
+    pub fn is_in_range(&self, created_at: DateTime<FixedOffset>) -> bool {
+        !self.is_before_range(created_at) && !self.is_after_range(created_at)
+    }
+
+    /// Whether `issue` is skipped because of [`Self::exclude_anomalous`].
+    pub fn is_excluded_as_anomalous(&self, issue: &IssueWithTimelineItems) -> bool {
+        if !self.exclude_anomalous {
+            return false;
+        }
+        let item_types: Vec<_> = self.variables["timelineItemTypes"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|item_type| item_type.as_str())
+            .collect();
+        issue.has_anomalies(&item_types)
+    }
+}
+
+/// A hash that, unlike [`std::hash::DefaultHasher`], is stable across Rust
+/// versions, so that cache entries stay valid.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
 impl PagedIssueWithTimelineItems {
+    /// `variables` must be the ones of the query that returned `self`, so that
+    /// the remaining pages come from the same repository and contain the same
+    /// kind of items.
     pub async fn collect_pages(
         &mut self,
         github: &GitHub,
-    ) -> octocrab::Result<IssueWithTimelineItems> {
+        variables: &serde_json::Value,
+    ) -> anyhow::Result<IssueWithTimelineItems> {
         let mut page_info = self.timeline_items.page_info.clone();
 
         loop {
@@ -100,17 +454,21 @@ impl PagedIssueWithTimelineItems {
                 break;
             }
 
+            let mut timeline_variables = serde_json::json!({
+                "number": self.number,
+                "after": page_info.end_cursor,
+                "timelineItemTypes": variables["timelineItemTypes"],
+            });
+            for key in ["owner", "name", "timeline_page_size"] {
+                if let Some(value) = variables.get(key) {
+                    timeline_variables[key] = value.clone();
+                }
+            }
+
             let issue_data: PagedIssueWithTimelineItems = github
-                .query(
-                    queries::TIMELINE_QUERY,
-                    serde_json::json!({
-                        "number": self.number,
-                        "after": page_info.end_cursor,
-                    }),
-                )
+                .query(queries::TIMELINE_QUERY, timeline_variables)
                 .await?
-                .get(&["repository", "issue"])
-                .unwrap();
+                .get(&["repository", "issue"])?;
 
             assert_eq!(issue_data.number, self.number);
             assert_eq!(issue_data.title, self.title);
@@ -122,11 +480,35 @@ impl PagedIssueWithTimelineItems {
             page_info = issue_data.timeline_items.page_info.clone();
         }
 
+        while let Some(page_info) = self.labels.page_info.clone().filter(|p| p.has_next_page) {
+            let mut label_variables = serde_json::json!({
+                "number": self.number,
+                "after": page_info.end_cursor,
+            });
+            for key in ["owner", "name", "label_page_size"] {
+                if let Some(value) = variables.get(key) {
+                    label_variables[key] = value.clone();
+                }
+            }
+
+            let labels: PagedLabels = github
+                .query(queries::LABELS_QUERY, label_variables)
+                .await?
+                .get(&["repository", "issue", "labels"])?;
+
+            self.labels.nodes.extend(labels.nodes);
+            self.labels.page_info = labels.page_info;
+        }
+
         Ok(IssueWithTimelineItems {
             url: self.url.clone(),
             number: self.number,
             title: self.title.clone(),
-            labels: self.labels.clone(),
+            author: self.author.clone(),
+            labels: Labels {
+                nodes: self.labels.nodes.clone(),
+                complete: self.labels.page_info.as_ref().map(|p| !p.has_next_page),
+            },
             created_at: self.created_at,
             timeline_items: self.timeline_items.nodes.clone(),
         })
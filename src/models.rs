@@ -1,11 +1,22 @@
 use chrono::{DateTime, FixedOffset};
 
+use serde::de::DeserializeOwned;
 use serde::de::Error;
 use serde::{Deserialize, Deserializer, Serialize};
 use std::fmt::{Display, Formatter};
+use std::str::FromStr;
 
 pub const E_NEEDS_MCVE: &str = "E-needs-mcve";
 
+/// The `itemTypes` that [`TimelineItem`] can be deserialized from.
+pub const TIMELINE_ITEM_TYPES: &[&str] = &[
+    "LABELED_EVENT",
+    "UNLABELED_EVENT",
+    "CLOSED_EVENT",
+    "REOPENED_EVENT",
+    "ISSUE_COMMENT",
+];
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct QueryResponse {
     pub data: Option<serde_json::Value>,
@@ -15,14 +26,32 @@ pub struct QueryResponse {
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Issues {
+    /// All issues that match `states` and `filterBy`, on all pages. `None` in
+    /// responses cached before it was queried.
+    #[serde(default)]
+    pub total_count: Option<u64>,
     pub nodes: Vec<PagedIssueWithTimelineItems>,
     pub page_info: PreviousPageInfo,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Labels {
     pub nodes: Vec<Label>,
+    /// Whether `nodes` are all labels of the issue. `None` in data from before
+    /// labels were paged, when only the first 100 labels were fetched.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub complete: Option<bool>,
+}
+
+/// A page of [`Labels`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PagedLabels {
+    pub nodes: Vec<Label>,
+    /// `None` in responses cached before it was queried.
+    #[serde(default)]
+    pub page_info: Option<NextPageInfo>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -33,22 +62,31 @@ pub struct TimelineItems {
 }
 
 /// TODO: Add more events
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 #[serde(tag = "__typename")]
 pub enum TimelineItem {
     LabeledEvent {
         #[serde(rename = "createdAt", deserialize_with = "from_rfc3339_str")]
         created_at: DateTime<FixedOffset>,
         label: Label,
+        /// `None` if not queried for, or if the account has been deleted.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        actor: Option<Actor>,
     },
     UnlabeledEvent {
         #[serde(rename = "createdAt", deserialize_with = "from_rfc3339_str")]
         created_at: DateTime<FixedOffset>,
         label: Label,
+        /// `None` if not queried for, or if the account has been deleted.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        actor: Option<Actor>,
     },
     ClosedEvent {
         #[serde(rename = "createdAt", deserialize_with = "from_rfc3339_str")]
         created_at: DateTime<FixedOffset>,
+        /// `None` if not queried for, or if the account has been deleted.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        actor: Option<Actor>,
     },
     ReopenedEvent {
         #[serde(rename = "createdAt", deserialize_with = "from_rfc3339_str")]
@@ -58,6 +96,15 @@ pub enum TimelineItem {
     IssueComment {
         #[serde(rename = "createdAt", deserialize_with = "from_rfc3339_str")]
         created_at: DateTime<FixedOffset>,
+        /// `None` if the account has been deleted.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        author: Option<Actor>,
+        #[serde(
+            rename = "authorAssociation",
+            default,
+            skip_serializing_if = "Option::is_none"
+        )]
+        author_association: Option<AuthorAssociation>,
     },
 }
 
@@ -66,9 +113,62 @@ pub struct Label {
     pub name: String,
 }
 
+/// The category of a label in the rust-lang label taxonomy, which is encoded as
+/// a prefix of the label name.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+pub enum LabelCategory {
+    /// `A-*`
+    Area,
+    /// `C-*`
+    Category,
+    /// `E-*`
+    Experience,
+    /// `I-*`
+    Issue,
+    /// `P-*`
+    Priority,
+    /// `S-*`
+    Status,
+    /// `T-*`
+    Team,
+    /// `WG-*`
+    WorkingGroup,
+    /// `F-*`
+    Feature,
+    /// `L-*`
+    Lint,
+    /// `O-*`
+    OperatingSystem,
+    /// `regression-*`
+    Regression,
+    /// `beta-*` and `stable-*`
+    Backport,
+    /// Labels outside of the taxonomy, like `needs-triage`.
+    Other,
+}
+
 #[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub struct Actor {
     pub login: String,
+    /// `User`, `Bot`, etc. Only present if the query asks for `__typename`.
+    #[serde(rename = "__typename", default)]
+    pub typename: Option<String>,
+}
+
+/// The relation of a comment author to the repository.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum AuthorAssociation {
+    Collaborator,
+    Contributor,
+    FirstTimer,
+    FirstTimeContributor,
+    Mannequin,
+    Member,
+    Owner,
+    None,
+    #[serde(other)]
+    Unknown,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -91,17 +191,27 @@ pub struct PagedIssueWithTimelineItems {
     pub url: String,
     pub number: u32,
     pub title: String,
-    pub labels: Labels,
+    /// `None` if the account has been deleted.
+    #[serde(default)]
+    pub author: Option<Actor>,
+    pub labels: PagedLabels,
     #[serde(rename = "createdAt", deserialize_with = "from_rfc3339_str")]
     pub created_at: DateTime<FixedOffset>,
     pub timeline_items: TimelineItems,
 }
 
+/// An issue with all pages of timeline items collected. Serializes to the same
+/// field names as [`PagedIssueWithTimelineItems`], except that the timeline
+/// items are a plain array.
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct IssueWithTimelineItems {
     pub url: String,
     pub number: u32,
     pub title: String,
+    pub author: Option<Actor>,
     pub labels: Labels,
+    #[serde(deserialize_with = "from_rfc3339_str")]
     pub created_at: DateTime<FixedOffset>,
     pub timeline_items: Vec<TimelineItem>,
 }
@@ -110,28 +220,321 @@ fn from_rfc3339_str<'de, D>(deserializer: D) -> Result<DateTime<FixedOffset>, D:
 where
     D: Deserializer<'de>,
 {
-    let s: &str = Deserialize::deserialize(deserializer)?;
-    DateTime::parse_from_rfc3339(s).map_err(D::Error::custom)
+    let s: String = Deserialize::deserialize(deserializer)?;
+    DateTime::parse_from_rfc3339(&s).map_err(D::Error::custom)
 }
 
 impl QueryResponse {
-    pub fn get<'de, T: Deserialize<'de>>(
-        &'de self,
+    /// The data at `path`. Fails with [`GraphQLErrors`] only if there are
+    /// errors about `path`, what it is in, or what is in it, so that errors
+    /// about other parts of the response do not reject valid data.
+    pub fn get<'de, T: Deserialize<'de>>(&'de self, path: &[&str]) -> anyhow::Result<T> {
+        let errors = self.errors_about(path, |_| true);
+        if !errors.is_empty() {
+            return Err(GraphQLErrors(errors).into());
+        }
+        Ok(T::deserialize(self.value(path)?)?)
+    }
+
+    /// Like [`Self::get`] for a connection at `path`, but nodes with errors are
+    /// removed instead of rejecting all nodes. Returns the removed nodes, which
+    /// may be `null`, with their errors.
+    pub fn get_skipping_nodes<T: DeserializeOwned>(
+        &self,
         path: &[&str],
-    ) -> Result<T, <serde_json::Value as Deserializer<'de>>::Error> {
-        if let Some(errors) = &self.errors {
-            return Err(<serde_json::Value as Deserializer<'de>>::Error::custom(
-                format!("Got errors: {:#?}", errors),
-            ));
+    ) -> anyhow::Result<(T, Vec<(serde_json::Value, GraphQLErrors)>)> {
+        let node_index =
+            |error_path: &[serde_json::Value]| match error_path.get(path.len()..path.len() + 2)? {
+                [nodes, index] if nodes == "nodes" => index.as_u64(),
+                _ => None,
+            };
+        let errors = self.errors_about(path, |error_path| node_index(error_path).is_none());
+        if !errors.is_empty() {
+            return Err(GraphQLErrors(errors).into());
+        }
+
+        let mut value = self.value(path)?.clone();
+        let mut skipped: Vec<(u64, GraphQLErrors)> = vec![];
+        for error in self.errors.iter().flatten() {
+            let Some(index) = error["path"].as_array().and_then(|p| node_index(p)) else {
+                continue;
+            };
+            match skipped.iter_mut().find(|(i, _)| *i == index) {
+                Some((_, errors)) => errors.0.push(error.clone()),
+                None => skipped.push((index, GraphQLErrors(vec![error.clone()]))),
+            }
+        }
+        // From the back, so that the indices stay valid.
+        skipped.sort_by_key(|(index, _)| std::cmp::Reverse(*index));
+        let mut removed = vec![];
+        if let Some(nodes) = value["nodes"].as_array_mut() {
+            for (index, errors) in skipped {
+                if let Some(index) = usize::try_from(index).ok().filter(|i| *i < nodes.len()) {
+                    removed.push((nodes.remove(index), errors));
+                }
+            }
         }
+        removed.reverse();
 
-        let mut value = self.data.as_ref().unwrap();
+        Ok((serde_json::from_value(value)?, removed))
+    }
+
+    fn value(&self, path: &[&str]) -> anyhow::Result<&serde_json::Value> {
+        let mut value = self
+            .data
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("no data in response, for {path:?}"))?;
         for segment in path {
-            let object = value.as_object().unwrap();
-            value = object.get(*segment).unwrap();
+            value = value
+                .get(*segment)
+                .ok_or_else(|| anyhow::anyhow!("no {segment:?} in response data, in {path:?}"))?;
+        }
+        Ok(value)
+    }
+
+    /// Errors without a path, and errors with a path that `path` is in or that
+    /// is in `path`, for which `filter` returns `true`.
+    fn errors_about(
+        &self,
+        path: &[&str],
+        filter: impl Fn(&[serde_json::Value]) -> bool,
+    ) -> Vec<serde_json::Value> {
+        self.errors
+            .iter()
+            .flatten()
+            .filter(|error| match error["path"].as_array() {
+                Some(error_path) => {
+                    error_path
+                        .iter()
+                        .zip(path)
+                        .all(|(a, b)| a.as_str() == Some(*b))
+                        && filter(error_path)
+                }
+                None => true,
+            })
+            .cloned()
+            .collect()
+    }
+}
+
+/// Errors that GitHub returned instead of (some of) the requested data.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GraphQLErrors(pub Vec<serde_json::Value>);
+
+impl GraphQLErrors {
+    /// Whether all errors are about specific data, like an issue that does not
+    /// exist anymore, rather than about the whole query.
+    pub fn are_about_data(&self) -> bool {
+        self.0.iter().all(|error| error["path"].is_array())
+    }
+
+    pub fn messages(&self) -> Vec<&str> {
+        self.0
+            .iter()
+            .map(|error| error["message"].as_str().unwrap_or("unknown error"))
+            .collect()
+    }
+}
+
+impl Display for GraphQLErrors {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "GitHub returned errors: {}", self.messages().join("; "))
+    }
+}
+
+impl std::error::Error for GraphQLErrors {}
+
+/// An issue that [`GitHub::for_issues_with_timeline`](crate::GitHub::for_issues_with_timeline)
+/// did not pass to the handler because GitHub returned errors for it, e.g.
+/// because it was deleted or transferred while paging. Issues whose timeline
+/// could only be fetched in part are skipped too.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SkippedIssue {
+    /// `None` if not even the number was returned.
+    pub number: Option<u32>,
+    pub errors: Vec<String>,
+}
+
+impl SkippedIssue {
+    pub fn new(number: Option<u32>, errors: &GraphQLErrors) -> Self {
+        Self {
+            number,
+            errors: errors.messages().into_iter().map(str::to_owned).collect(),
+        }
+    }
+}
+
+impl Display for SkippedIssue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.number {
+            Some(number) => write!(f, "#{number}")?,
+            None => write!(f, "an unknown issue")?,
+        }
+        write!(f, ": {}", self.errors.join("; "))
+    }
+}
+
+/// Accounts that are users as far as GitHub is concerned, but that are run by
+/// automation in rust-lang.
+const KNOWN_BOTS: &[&str] = &[
+    "bors",
+    "rfcbot",
+    "rust-highfive",
+    "rust-log-analyzer",
+    "rust-timer",
+    "rustbot",
+];
+
+/// Prefixes that are stripped by [`Label::suffix`]. Backport labels keep their
+/// prefix since `beta` and `stable` is what they are about.
+const LABEL_CATEGORY_PREFIXES: &[(&str, LabelCategory)] = &[
+    ("A-", LabelCategory::Area),
+    ("C-", LabelCategory::Category),
+    ("E-", LabelCategory::Experience),
+    ("I-", LabelCategory::Issue),
+    ("P-", LabelCategory::Priority),
+    ("S-", LabelCategory::Status),
+    ("T-", LabelCategory::Team),
+    ("WG-", LabelCategory::WorkingGroup),
+    ("F-", LabelCategory::Feature),
+    ("L-", LabelCategory::Lint),
+    ("O-", LabelCategory::OperatingSystem),
+    ("regression-", LabelCategory::Regression),
+];
+
+impl LabelCategory {
+    pub fn of(name: &str) -> Self {
+        if let Some((_, category)) = LABEL_CATEGORY_PREFIXES
+            .iter()
+            .find(|(prefix, _)| name.starts_with(prefix))
+        {
+            *category
+        } else if name.starts_with("beta-") || name.starts_with("stable-") {
+            LabelCategory::Backport
+        } else {
+            LabelCategory::Other
+        }
+    }
+
+    fn prefix(&self) -> Option<&'static str> {
+        LABEL_CATEGORY_PREFIXES
+            .iter()
+            .find(|(_, category)| category == self)
+            .map(|(prefix, _)| *prefix)
+    }
+}
+
+impl Labels {
+    /// All labels of an issue.
+    pub const fn new(nodes: Vec<Label>) -> Self {
+        Self {
+            nodes,
+            complete: Some(true),
+        }
+    }
+
+    /// Whether `nodes` are all labels of the issue. Without the `complete`
+    /// flag, fewer than 100 labels must be all of them.
+    pub fn is_complete(&self) -> bool {
+        self.complete.unwrap_or(self.nodes.len() < 100)
+    }
+}
+
+impl Label {
+    pub fn category(&self) -> LabelCategory {
+        LabelCategory::of(&self.name)
+    }
+
+    /// The label name without its category prefix, e.g. `compiler` for
+    /// `T-compiler`.
+    pub fn suffix(&self) -> &str {
+        match self.category().prefix() {
+            Some(prefix) => &self.name[prefix.len()..],
+            None => &self.name,
         }
+    }
+}
 
-        T::deserialize(value)
+impl Actor {
+    pub fn is_bot(&self) -> bool {
+        self.typename.as_deref() == Some("Bot") || KNOWN_BOTS.contains(&self.login.as_str())
+    }
+}
+
+impl AuthorAssociation {
+    /// Whether the author is a member of the organization owning the repository
+    /// or has been given write access to it.
+    pub fn is_member(&self) -> bool {
+        matches!(
+            self,
+            AuthorAssociation::Collaborator | AuthorAssociation::Member | AuthorAssociation::Owner
+        )
+    }
+}
+
+/// Parses the prefix of a category, with or without the trailing dash, e.g.
+/// `T` or `T-`. `backport` and `other` are accepted for the categories that
+/// have no single prefix.
+impl FromStr for LabelCategory {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "backport" | "beta" | "stable" => return Ok(LabelCategory::Backport),
+            "other" => return Ok(LabelCategory::Other),
+            _ => {}
+        }
+
+        let prefix = format!("{}-", s.trim_end_matches('-'));
+        LABEL_CATEGORY_PREFIXES
+            .iter()
+            .find(|(candidate, _)| *candidate == prefix)
+            .map(|(_, category)| *category)
+            .ok_or_else(|| format!("unknown label category: {s}"))
+    }
+}
+
+impl Display for LabelCategory {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match (self, self.prefix()) {
+            (_, Some(prefix)) => write!(f, "{prefix}*"),
+            (LabelCategory::Backport, None) => f.write_str("beta-*/stable-*"),
+            _ => f.write_str("other"),
+        }
+    }
+}
+
+impl TimelineItem {
+    pub fn created_at(&self) -> DateTime<FixedOffset> {
+        match self {
+            TimelineItem::LabeledEvent { created_at, .. }
+            | TimelineItem::UnlabeledEvent { created_at, .. }
+            | TimelineItem::ClosedEvent { created_at, .. }
+            | TimelineItem::ReopenedEvent { created_at, .. }
+            | TimelineItem::IssueComment { created_at, .. } => *created_at,
+        }
+    }
+
+    /// Who caused the item, if known.
+    pub fn actor(&self) -> Option<&Actor> {
+        match self {
+            TimelineItem::LabeledEvent { actor, .. }
+            | TimelineItem::UnlabeledEvent { actor, .. }
+            | TimelineItem::ClosedEvent { actor, .. } => actor.as_ref(),
+            TimelineItem::ReopenedEvent { actor, .. } => Some(actor),
+            TimelineItem::IssueComment { author, .. } => author.as_ref(),
+        }
+    }
+
+    /// The `itemTypes` value that makes queries return this kind of item.
+    pub fn item_type(&self) -> &'static str {
+        match self {
+            TimelineItem::LabeledEvent { .. } => "LABELED_EVENT",
+            TimelineItem::UnlabeledEvent { .. } => "UNLABELED_EVENT",
+            TimelineItem::ClosedEvent { .. } => "CLOSED_EVENT",
+            TimelineItem::ReopenedEvent { .. } => "REOPENED_EVENT",
+            TimelineItem::IssueComment { .. } => "ISSUE_COMMENT",
+        }
     }
 }
 
@@ -144,14 +547,30 @@ impl Display for Label {
 impl Display for TimelineItem {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
-            TimelineItem::LabeledEvent { label, created_at } => {
-                write!(f, "+{} {}", label, created_at.format("%Y-%m-%d"))
-            }
-            TimelineItem::UnlabeledEvent { label, created_at } => {
-                write!(f, "-{} {}", label, created_at.format("%Y-%m-%d"))
-            }
-            TimelineItem::ClosedEvent { created_at } => {
-                write!(f, "<CLOSED> {}", created_at.format("%Y-%m-%d"))
+            TimelineItem::LabeledEvent {
+                label,
+                created_at,
+                actor,
+            } => write!(
+                f,
+                "+{}{} {}",
+                label,
+                by(actor),
+                created_at.format("%Y-%m-%d")
+            ),
+            TimelineItem::UnlabeledEvent {
+                label,
+                created_at,
+                actor,
+            } => write!(
+                f,
+                "-{}{} {}",
+                label,
+                by(actor),
+                created_at.format("%Y-%m-%d")
+            ),
+            TimelineItem::ClosedEvent { created_at, actor } => {
+                write!(f, "<CLOSED{}> {}", by(actor), created_at.format("%Y-%m-%d"))
             }
             TimelineItem::ReopenedEvent { created_at, actor } => {
                 write!(
@@ -161,13 +580,29 @@ impl Display for TimelineItem {
                     created_at.format("%Y-%m-%d")
                 )
             }
-            TimelineItem::IssueComment { created_at } => {
-                write!(f, "<COMMENT> {}", created_at.format("%Y-%m-%d"))
-            }
+            TimelineItem::IssueComment {
+                created_at, author, ..
+            } => match author {
+                Some(author) => write!(
+                    f,
+                    "<COMMENT by {}> {}",
+                    author.login,
+                    created_at.format("%Y-%m-%d")
+                ),
+                None => write!(f, "<COMMENT> {}", created_at.format("%Y-%m-%d")),
+            },
         }
     }
 }
 
+/// ` by login`, or nothing if the actor is unknown.
+fn by(actor: &Option<Actor>) -> String {
+    actor
+        .as_ref()
+        .map(|actor| format!(" by {}", actor.login))
+        .unwrap_or_default()
+}
+
 impl Display for IssueWithTimelineItems {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         write!(
@@ -184,3 +619,134 @@ impl Display for IssueWithTimelineItems {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn label(name: &str) -> Label {
+        Label {
+            name: name.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_label_category() {
+        let cases = [
+            ("A-diagnostics", LabelCategory::Area, "diagnostics"),
+            ("C-bug", LabelCategory::Category, "bug"),
+            ("E-needs-mcve", LabelCategory::Experience, "needs-mcve"),
+            ("I-ICE", LabelCategory::Issue, "ICE"),
+            ("P-high", LabelCategory::Priority, "high"),
+            ("S-blocked", LabelCategory::Status, "blocked"),
+            ("T-compiler", LabelCategory::Team, "compiler"),
+            ("WG-async", LabelCategory::WorkingGroup, "async"),
+            ("F-never_type", LabelCategory::Feature, "never_type"),
+            ("L-unused_imports", LabelCategory::Lint, "unused_imports"),
+            ("O-windows", LabelCategory::OperatingSystem, "windows"),
+            (
+                "regression-from-stable-to-beta",
+                LabelCategory::Regression,
+                "from-stable-to-beta",
+            ),
+            ("beta-nominated", LabelCategory::Backport, "beta-nominated"),
+            (
+                "stable-accepted",
+                LabelCategory::Backport,
+                "stable-accepted",
+            ),
+            ("needs-triage", LabelCategory::Other, "needs-triage"),
+        ];
+
+        for (name, category, suffix) in cases {
+            assert_eq!(label(name).category(), category, "{name}");
+            assert_eq!(label(name).suffix(), suffix, "{name}");
+        }
+    }
+
+    #[test]
+    fn test_label_category_from_str() {
+        assert_eq!("T".parse(), Ok(LabelCategory::Team));
+        assert_eq!("WG-".parse(), Ok(LabelCategory::WorkingGroup));
+        assert_eq!("regression".parse(), Ok(LabelCategory::Regression));
+        assert_eq!("backport".parse(), Ok(LabelCategory::Backport));
+        assert!("X".parse::<LabelCategory>().is_err());
+    }
+
+    #[test]
+    fn test_labels_is_complete() {
+        assert!(Labels::new(vec![label("C-bug")]).is_complete());
+
+        // From before labels were paged.
+        let dumped: Labels = serde_json::from_str(r#"{ "nodes": [] }"#).unwrap();
+        assert!(dumped.is_complete());
+        let truncated = Labels {
+            nodes: vec![label("C-bug"); 100],
+            complete: None,
+        };
+        assert!(!truncated.is_complete());
+        assert!(!Labels {
+            complete: Some(false),
+            ..Labels::new(vec![])
+        }
+        .is_complete());
+    }
+
+    #[test]
+    fn test_comment_by_deleted_account() {
+        let comment = TimelineItem::IssueComment {
+            created_at: DateTime::parse_from_rfc3339("2020-01-01T00:00:00Z").unwrap(),
+            author: None,
+            author_association: None,
+        };
+        let json = serde_json::to_value(&comment).unwrap();
+        assert_eq!(json.get("author"), None);
+        assert_eq!(json.get("authorAssociation"), None);
+        assert_eq!(
+            serde_json::from_value::<TimelineItem>(json).unwrap(),
+            comment
+        );
+    }
+
+    #[test]
+    fn test_query_response_get() {
+        let response: QueryResponse = serde_json::from_value(serde_json::json!({
+            "data": {
+                "repository": {
+                    "issues": { "nodes": [{ "number": 1 }, null, { "number": 3 }] },
+                    "issue": null,
+                },
+            },
+            "errors": [
+                { "path": ["repository", "issues", "nodes", 1], "message": "forbidden" },
+                { "path": ["repository", "issue"], "message": "not found" },
+            ],
+        }))
+        .unwrap();
+
+        // Only errors about the requested data count.
+        let error = response
+            .get::<serde_json::Value>(&["repository", "issue"])
+            .unwrap_err();
+        assert_eq!(error.to_string(), "GitHub returned errors: not found");
+        assert!(error
+            .downcast_ref::<GraphQLErrors>()
+            .unwrap()
+            .are_about_data());
+        assert!(response
+            .get::<serde_json::Value>(&["repository", "issues"])
+            .is_err());
+        assert!(response.get::<serde_json::Value>(&["repository"]).is_err());
+
+        let (issues, skipped): (serde_json::Value, _) = response
+            .get_skipping_nodes(&["repository", "issues"])
+            .unwrap();
+        assert_eq!(
+            issues,
+            serde_json::json!({ "nodes": [{ "number": 1 }, { "number": 3 }] })
+        );
+        assert_eq!(skipped.len(), 1);
+        assert_eq!(skipped[0].0, serde_json::Value::Null);
+        assert_eq!(skipped[0].1.messages(), ["forbidden"]);
+    }
+}
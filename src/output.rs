@@ -0,0 +1,190 @@
+//! Printing of results in a format chosen with `--format`.
+//!
+//! * `text` uses the `Display` impl of the record.
+//! * `json` prints one JSON object per line (JSON Lines).
+//! * `csv` prints a header based on the first record. Nested objects are
+//!   flattened with `.` separated column names, and arrays are put in a single
+//!   column as JSON, so that the columns do not depend on the data.
+
+use serde::Serialize;
+use std::fmt::Display;
+use std::io::{Stdout, Write};
+
+#[derive(clap::ValueEnum, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    #[default]
+    Text,
+    Json,
+    Csv,
+}
+
+pub struct Output<W: Write> {
+    format: OutputFormat,
+    writer: W,
+    csv_header: Option<Vec<String>>,
+}
+
+impl Output<Stdout> {
+    pub fn stdout(format: OutputFormat) -> Self {
+        Self::new(format, std::io::stdout())
+    }
+}
+
+impl<W: Write> Output<W> {
+    pub fn new(format: OutputFormat, writer: W) -> Self {
+        Self {
+            format,
+            writer,
+            csv_header: None,
+        }
+    }
+
+    pub fn print(&mut self, record: &(impl Serialize + Display)) -> anyhow::Result<()> {
+        match self.format {
+            OutputFormat::Text => writeln!(self.writer, "{record}")?,
+            OutputFormat::Json => {
+                serde_json::to_writer(&mut self.writer, record)?;
+                writeln!(self.writer)?;
+            }
+            OutputFormat::Csv => {
+                let mut columns = vec![];
+                flatten(String::new(), serde_json::to_value(record)?, &mut columns);
+
+                let header = match &self.csv_header {
+                    Some(header) => header,
+                    None => {
+                        let header = columns.iter().map(|(name, _)| name.clone()).collect();
+                        let header = self.csv_header.insert(header);
+                        writeln!(self.writer, "{}", csv_row(header.iter()))?;
+                        header
+                    }
+                };
+
+                if columns.len() != header.len()
+                    || columns.iter().zip(header).any(|((name, _), h)| name != h)
+                {
+                    anyhow::bail!("CSV columns changed between records: {columns:?}");
+                }
+                writeln!(
+                    self.writer,
+                    "{}",
+                    csv_row(columns.iter().map(|(_, value)| value))
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+fn flatten(name: String, value: serde_json::Value, columns: &mut Vec<(String, String)>) {
+    match value {
+        serde_json::Value::Object(object) => {
+            for (key, value) in object {
+                let name = if name.is_empty() {
+                    key
+                } else {
+                    format!("{name}.{key}")
+                };
+                flatten(name, value, columns);
+            }
+        }
+        serde_json::Value::Null => columns.push((name, String::new())),
+        serde_json::Value::String(s) => columns.push((name, s)),
+        value => columns.push((name, value.to_string())),
+    }
+}
+
+fn csv_row<'a>(fields: impl Iterator<Item = &'a String>) -> String {
+    fields
+        .map(|field| {
+            if field.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", field.replace('"', "\"\""))
+            } else {
+                field.clone()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Serialize)]
+    struct Record {
+        name: String,
+        nested: Nested,
+        list: Vec<u32>,
+        missing: Option<f64>,
+    }
+
+    #[derive(Serialize)]
+    struct Nested {
+        value: f64,
+    }
+
+    impl Display for Record {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "{} is {}", self.name, self.nested.value)
+        }
+    }
+
+    fn print(format: OutputFormat, records: &[Record]) -> String {
+        let mut output = Output::new(format, vec![]);
+        for record in records {
+            output.print(record).unwrap();
+        }
+        String::from_utf8(output.into_inner()).unwrap()
+    }
+
+    fn records() -> Vec<Record> {
+        vec![
+            Record {
+                name: "a, \"quoted\"".to_string(),
+                nested: Nested { value: 1.5 },
+                list: vec![1, 2],
+                missing: None,
+            },
+            Record {
+                name: "b".to_string(),
+                nested: Nested { value: f64::NAN },
+                list: vec![],
+                missing: Some(2.0),
+            },
+        ]
+    }
+
+    #[test]
+    fn test_text() {
+        assert_eq!(
+            print(OutputFormat::Text, &records()),
+            "a, \"quoted\" is 1.5\nb is NaN\n"
+        );
+    }
+
+    #[test]
+    fn test_json() {
+        assert_eq!(
+            print(OutputFormat::Json, &records()),
+            r#"{"name":"a, \"quoted\"","nested":{"value":1.5},"list":[1,2],"missing":null}
+{"name":"b","nested":{"value":null},"list":[],"missing":2.0}
+"#
+        );
+    }
+
+    #[test]
+    fn test_csv() {
+        assert_eq!(
+            print(OutputFormat::Csv, &records()),
+            r#"name,nested.value,list,missing
+"a, ""quoted""",1.5,"[1,2]",
+b,,[],2.0
+"#
+        );
+    }
+}
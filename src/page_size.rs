@@ -0,0 +1,127 @@
+//! Page sizes that shrink when GitHub gives up on a query because it is too
+//! heavy, and grow back once queries succeed again.
+
+use crate::{GraphQLErrors, QueryResponse, RequestTimeout};
+
+/// The `$timeline_page_size` default of the queries.
+pub(crate) const DEFAULT_TIMELINE_PAGE_SIZE: u64 = 200;
+
+/// Parts of the GraphQL error messages and types GitHub returns when a query
+/// takes too long or needs too many resources.
+const TOO_HEAVY_ERRORS: &[&str] = &[
+    "Something went wrong while executing your query",
+    "Timeout on validation of query",
+    "RESOURCE_LIMITS_EXCEEDED",
+    "MAX_NODE_LIMIT_EXCEEDED",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct AdaptivePageSize {
+    max: u64,
+    current: u64,
+}
+
+impl AdaptivePageSize {
+    pub(crate) fn new(max: u64) -> Self {
+        Self { max, current: max }
+    }
+
+    pub(crate) fn get(self) -> u64 {
+        self.current
+    }
+
+    pub(crate) fn is_reduced(self) -> bool {
+        self.current < self.max
+    }
+
+    /// Halves the page size. Returns `false` if it is 1 already.
+    pub(crate) fn shrink(&mut self) -> bool {
+        let shrunk = (self.current / 2).max(1);
+        let changed = shrunk != self.current;
+        self.current = shrunk;
+        changed
+    }
+
+    /// Doubles the page size, up to the original one.
+    pub(crate) fn grow(&mut self) {
+        self.current = (self.current * 2).min(self.max);
+    }
+}
+
+/// Whether a query failed because it was too heavy, so that it may succeed with
+/// smaller pages. Only GraphQL errors and [`RequestTimeout`] are considered;
+/// other failures such as network errors are not retried with smaller pages.
+pub(crate) fn is_too_heavy(response: &anyhow::Result<QueryResponse>) -> bool {
+    match response {
+        Ok(response) => response.errors.iter().flatten().any(is_too_heavy_error),
+        Err(e) => {
+            e.is::<RequestTimeout>()
+                || e.downcast_ref::<GraphQLErrors>()
+                    .is_some_and(|errors| errors.0.iter().any(is_too_heavy_error))
+        }
+    }
+}
+
+fn is_too_heavy_error(error: &serde_json::Value) -> bool {
+    [&error["type"], &error["message"]]
+        .iter()
+        .filter_map(|value| value.as_str())
+        .any(|text| TOO_HEAVY_ERRORS.iter().any(|part| text.contains(part)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_adaptive_page_size() {
+        let mut size = AdaptivePageSize::new(10);
+        assert!(size.shrink());
+        assert!(size.shrink());
+        assert_eq!((size.get(), size.is_reduced()), (2, true));
+        assert!(size.shrink());
+        assert!(!size.shrink());
+        assert_eq!(size.get(), 1);
+
+        for expected in [2, 4, 8, 10, 10] {
+            size.grow();
+            assert_eq!(size.get(), expected);
+        }
+        assert!(!size.is_reduced());
+    }
+
+    #[test]
+    fn test_is_too_heavy() {
+        let response = |errors: serde_json::Value| {
+            Ok(QueryResponse {
+                data: None,
+                errors: serde_json::from_value(errors).unwrap(),
+            })
+        };
+        assert!(is_too_heavy(&response(serde_json::json!([{
+            "message": "Something went wrong while executing your query. This may be the result of a timeout, or it could be a GitHub bug."
+        }]))));
+        assert!(is_too_heavy(&response(serde_json::json!([{
+            "type": "RESOURCE_LIMITS_EXCEEDED",
+            "message": "This query requires more resources than are available."
+        }]))));
+        assert!(!is_too_heavy(&response(serde_json::json!([{
+            "type": "NOT_FOUND",
+            "message": "Could not resolve to a Repository with the name 'rust-lang/rust'."
+        }]))));
+        assert!(!is_too_heavy(&response(serde_json::Value::Null)));
+
+        assert!(is_too_heavy(&Err(RequestTimeout(
+            std::time::Duration::from_secs(1)
+        )
+        .into())));
+        assert!(!is_too_heavy(&Err(anyhow::anyhow!("connection refused"))));
+        assert!(!is_too_heavy(&Err(anyhow::anyhow!(
+            "error trying to connect: connection timed out"
+        ))));
+        assert!(is_too_heavy(&Err(anyhow::Error::new(GraphQLErrors(vec![
+            serde_json::json!({"type": "MAX_NODE_LIMIT_EXCEEDED", "message": "Too many nodes."})
+        ]))
+        .context("Fetching page 3"))));
+    }
+}
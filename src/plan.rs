@@ -0,0 +1,127 @@
+//! Estimates of what going through the issues of an [`IssuesQuery`] costs, to
+//! check before starting a long run.
+
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::fmt::Display;
+
+use crate::{queries, GitHub, IssuesQuery};
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Plan {
+    /// Issues that match `states` and `filterBy`. `created_since` and
+    /// `created_until` are not taken into account, so fewer issues may be
+    /// fetched.
+    pub matching_issues: u64,
+    pub page_size: u64,
+    pub pages: u64,
+    /// One per page. Issues with more timeline items than fit on the first
+    /// timeline page need more, and cached responses need none.
+    pub requests: u64,
+    /// GraphQL rate limit points.
+    pub cost: u64,
+    pub rate_limit_remaining: u64,
+    pub rate_limit: u64,
+    pub rate_limit_reset_at: DateTime<Utc>,
+}
+
+impl GitHub {
+    /// Estimates the cost of `for_issues_with_timeline` with `query`, with one
+    /// cheap request.
+    pub async fn plan(&self, query: &IssuesQuery) -> anyhow::Result<Plan> {
+        let mut variables = serde_json::json!({});
+        for key in ["owner", "name", "states", "filterBy"] {
+            if let Some(value) = query.variables.get(key) {
+                variables[key] = value.clone();
+            }
+        }
+        let data = self.query_uncached(queries::PLAN_QUERY, variables).await?;
+
+        let matching_issues = data["repository"]["issues"]["totalCount"]
+            .as_u64()
+            .context("no totalCount in response")?;
+        let page_size = query.variables["page_size"]
+            .as_u64()
+            .context("no page_size in query")?;
+        let rate_limit = &data["rateLimit"];
+        Ok(Plan::new(
+            matching_issues,
+            page_size,
+            query.pages as u64,
+            rate_limit["remaining"].as_u64().unwrap_or_default(),
+            rate_limit["limit"].as_u64().unwrap_or_default(),
+            serde_json::from_value(rate_limit["resetAt"].clone())
+                .context("no rateLimit.resetAt in response")?,
+        ))
+    }
+}
+
+impl Plan {
+    fn new(
+        matching_issues: u64,
+        page_size: u64,
+        max_pages: u64,
+        rate_limit_remaining: u64,
+        rate_limit: u64,
+        rate_limit_reset_at: DateTime<Utc>,
+    ) -> Self {
+        // Even without matching issues, one page is requested.
+        let pages = max_pages.min(matching_issues.div_ceil(page_size).max(1));
+        Self {
+            matching_issues,
+            page_size,
+            pages,
+            requests: pages,
+            cost: pages * page_cost(page_size),
+            rate_limit_remaining,
+            rate_limit,
+            rate_limit_reset_at,
+        }
+    }
+}
+
+/// The rate limit points of a page of `ISSUES_WITH_TIMELINE_QUERY`. GitHub
+/// adds up the requests needed for each connection as if it was full, i.e. one
+/// for the issues and one per issue each for labels and timeline items, and
+/// divides by 100.
+fn page_cost(page_size: u64) -> u64 {
+    ((1 + 2 * page_size) as f64 / 100.0).round().max(1.0) as u64
+}
+
+impl Display for Plan {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{} matching issues", self.matching_issues)?;
+        writeln!(
+            f,
+            "{} pages of {} issues take at least {} requests costing {} points",
+            self.pages, self.page_size, self.requests, self.cost
+        )?;
+        write!(
+            f,
+            "{} of {} points left until {}",
+            self.rate_limit_remaining, self.rate_limit, self.rate_limit_reset_at
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plan() {
+        let reset_at = "2023-11-01T00:00:00Z".parse().unwrap();
+        assert_eq!(page_cost(10), 1);
+        assert_eq!(page_cost(100), 2);
+
+        let plan = Plan::new(25_000, 10, 2500, 5000, 5000, reset_at);
+        assert_eq!((plan.pages, plan.requests, plan.cost), (2500, 2500, 2500));
+
+        let plan = Plan::new(25, 10, 2500, 5000, 5000, reset_at);
+        assert_eq!((plan.pages, plan.cost), (3, 3));
+
+        let plan = Plan::new(0, 100, 5, 5000, 5000, reset_at);
+        assert_eq!((plan.pages, plan.cost), (1, 2));
+    }
+}
@@ -0,0 +1,158 @@
+//! Progress of long runs, reported through a [`ProgressHook`] that
+//! [`GitHub::for_issues_with_timeline`](crate::GitHub::for_issues_with_timeline)
+//! calls.
+
+use std::io::{IsTerminal, Write};
+use std::time::{Duration, Instant};
+
+use crate::{ApiUsage, GitHub};
+
+/// How far [`GitHub::for_issues_with_timeline`](crate::GitHub::for_issues_with_timeline)
+/// has come.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Progress {
+    /// Issues on the pages so far, including those outside of the date range.
+    pub issues: u64,
+    pub pages: u64,
+    /// The issues on all pages that will be fetched, from `totalCount` and the
+    /// maximum number of pages. Fewer if the date range ends paging early.
+    pub expected_issues: Option<u64>,
+    pub expected_pages: Option<u64>,
+    pub api_usage: ApiUsage,
+    pub elapsed: Duration,
+}
+
+pub trait ProgressHook {
+    /// Called after each issue and each page.
+    fn update(&self, progress: &Progress);
+
+    /// Called before the handlers, which may print, and when done.
+    fn clear(&self) {}
+}
+
+/// Shows progress on one line of stderr that is redrawn on every update.
+pub struct ProgressDisplay;
+
+impl ProgressDisplay {
+    /// Whether stderr is a terminal, where redrawing works.
+    pub fn is_supported() -> bool {
+        std::io::stderr().is_terminal()
+    }
+}
+
+impl ProgressHook for ProgressDisplay {
+    fn update(&self, progress: &Progress) {
+        let _ = write!(std::io::stderr(), "\r\x1b[K{progress}");
+    }
+
+    fn clear(&self) {
+        let _ = write!(std::io::stderr(), "\r\x1b[K");
+    }
+}
+
+/// The hook of a [`GitHub`], if any, which is cleared when dropped so that
+/// errors are not printed after the progress line.
+pub(crate) struct ProgressGuard<'a>(pub(crate) Option<&'a (dyn ProgressHook + Send + Sync)>);
+
+impl ProgressGuard<'_> {
+    pub(crate) fn update(&self, progress: &mut Progress, github: &GitHub, started: Instant) {
+        if let Some(hook) = self.0 {
+            progress.api_usage = github.api_usage();
+            progress.elapsed = started.elapsed();
+            hook.update(progress);
+        }
+    }
+
+    pub(crate) fn clear(&self) {
+        if let Some(hook) = self.0 {
+            hook.clear();
+        }
+    }
+}
+
+impl Drop for ProgressGuard<'_> {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+impl Progress {
+    /// The remaining time if the remaining issues take as long as the previous
+    /// ones.
+    pub fn eta(&self) -> Option<Duration> {
+        let expected = self.expected_issues?;
+        if self.issues == 0 {
+            return None;
+        }
+        let remaining = expected.saturating_sub(self.issues);
+        Some(self.elapsed.mul_f64(remaining as f64 / self.issues as f64))
+    }
+}
+
+fn of(done: u64, expected: Option<u64>) -> String {
+    match expected {
+        Some(expected) => format!("{done}/{expected}"),
+        None => done.to_string(),
+    }
+}
+
+/// Like `1h 02m` or `3m 20s`.
+fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    match (seconds / 3600, seconds / 60 % 60, seconds % 60) {
+        (0, 0, s) => format!("{s}s"),
+        (0, m, s) => format!("{m}m {s:02}s"),
+        (h, m, _) => format!("{h}h {m:02}m"),
+    }
+}
+
+impl std::fmt::Display for Progress {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "issues {}, pages {}, requests {} ({} points), elapsed {}",
+            of(self.issues, self.expected_issues),
+            of(self.pages, self.expected_pages),
+            self.api_usage.requests,
+            self.api_usage.cost,
+            format_duration(self.elapsed)
+        )?;
+        if let Some(eta) = self.eta() {
+            write!(f, ", ETA {}", format_duration(eta))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_progress() {
+        let mut progress = Progress {
+            issues: 100,
+            pages: 10,
+            expected_issues: Some(400),
+            expected_pages: Some(40),
+            api_usage: ApiUsage {
+                requests: 12,
+                cost: 12,
+            },
+            elapsed: Duration::from_secs(65),
+        };
+        assert_eq!(progress.eta(), Some(Duration::from_secs(195)));
+        assert_eq!(
+            progress.to_string(),
+            "issues 100/400, pages 10/40, requests 12 (12 points), elapsed 1m 05s, ETA 3m 15s"
+        );
+
+        progress.expected_issues = None;
+        progress.expected_pages = None;
+        progress.elapsed = Duration::from_secs(7300);
+        assert_eq!(
+            progress.to_string(),
+            "issues 100, pages 10, requests 12 (12 points), elapsed 2h 01m"
+        );
+    }
+}
@@ -1,20 +1,36 @@
-pub const ISSUES_WITH_TIMELINE_QUERY: &str = r#" query ($page_size: Int!, $before: String, $states: [IssueState!], $filterBy: IssueFilters, $timeline_page_size: Int = 200, $timelineItemTypes: [IssueTimelineItemsItemType!]!) {
-    repository(owner: "rust-lang", name: "rust") {
+pub const ISSUES_WITH_TIMELINE_QUERY: &str = r#" query ($owner: String = "rust-lang", $name: String = "rust", $page_size: Int!, $before: String, $states: [IssueState!], $filterBy: IssueFilters, $timeline_page_size: Int = 200, $label_page_size: Int = 100, $timelineItemTypes: [IssueTimelineItemsItemType!]!) {
+    rateLimit {
+        cost
+    }
+    repository(owner: $owner, name: $name) {
         issues(last: $page_size, before: $before, states: $states, filterBy: $filterBy, orderBy: { field: CREATED_AT, direction: ASC }) {
+            totalCount
             nodes {
                 url
                 number
                 title
+                author {
+                    __typename
+                    login
+                }
                 createdAt
-                labels(first: 100) {
+                labels(first: $label_page_size) {
                     nodes {
                         name
                     }
+                    pageInfo {
+                        endCursor
+                        hasNextPage
+                    }
                 }
                 timelineItems(first: $timeline_page_size, itemTypes: $timelineItemTypes) {
                     nodes {
                         ... on LabeledEvent {
                             __typename
+                            actor {
+                                __typename
+                                login
+                            }
                             label {
                                 name
                             }
@@ -22,6 +38,10 @@ pub const ISSUES_WITH_TIMELINE_QUERY: &str = r#" query ($page_size: Int!, $befor
                         }
                         ... on UnlabeledEvent {
                             __typename
+                            actor {
+                                __typename
+                                login
+                            }
                             label {
                                 name
                             }
@@ -29,11 +49,16 @@ pub const ISSUES_WITH_TIMELINE_QUERY: &str = r#" query ($page_size: Int!, $befor
                         }
                         ... on ClosedEvent {
                             __typename
+                            actor {
+                                __typename
+                                login
+                            }
                             createdAt
                         }
                         ... on ReopenedEvent {
                             __typename
                             actor {
+                                __typename
                                 login
                             }
                             createdAt
@@ -41,6 +66,11 @@ pub const ISSUES_WITH_TIMELINE_QUERY: &str = r#" query ($page_size: Int!, $befor
                         ... on IssueComment {
                             __typename
                             createdAt
+                            author {
+                                __typename
+                                login
+                            }
+                            authorAssociation
                         }
                     }
                     pageInfo {
@@ -61,21 +91,37 @@ pub const ISSUES_WITH_TIMELINE_QUERY: &str = r#" query ($page_size: Int!, $befor
     }
 } "#;
 
-pub const TIMELINE_QUERY: &str = r#" query ($number: Int!, $after: String!, $timeline_page_size: Int = 200, $timelineItemTypes: [IssueTimelineItemsItemType!]!) {
-    repository(owner: "rust-lang", name: "rust") {
+pub const TIMELINE_QUERY: &str = r#" query ($owner: String = "rust-lang", $name: String = "rust", $number: Int!, $after: String!, $timeline_page_size: Int = 200, $label_page_size: Int = 100, $timelineItemTypes: [IssueTimelineItemsItemType!]!) {
+    rateLimit {
+        cost
+    }
+    repository(owner: $owner, name: $name) {
         issue(number: $number) {
+            url
             number
             title
+            author {
+                __typename
+                login
+            }
             createdAt
-            labels(first: 100) {
+            labels(first: $label_page_size) {
                 nodes {
                     name
                 }
+                pageInfo {
+                    endCursor
+                    hasNextPage
+                }
             }
             timelineItems(itemTypes: $timelineItemTypes, first: $timeline_page_size, after: $after) {
                 nodes {
                     ... on LabeledEvent {
                         __typename
+                        actor {
+                            __typename
+                            login
+                        }
                         createdAt
                         label {
                             name
@@ -83,6 +129,10 @@ pub const TIMELINE_QUERY: &str = r#" query ($number: Int!, $after: String!, $tim
                     }
                     ... on UnlabeledEvent {
                         __typename
+                        actor {
+                            __typename
+                            login
+                        }
                         createdAt
                         label {
                             name
@@ -90,8 +140,29 @@ pub const TIMELINE_QUERY: &str = r#" query ($number: Int!, $after: String!, $tim
                     }
                     ... on ClosedEvent {
                         __typename
+                        actor {
+                            __typename
+                            login
+                        }
                         createdAt
                     }
+                    ... on ReopenedEvent {
+                        __typename
+                        actor {
+                            __typename
+                            login
+                        }
+                        createdAt
+                    }
+                    ... on IssueComment {
+                        __typename
+                        createdAt
+                        author {
+                            __typename
+                            login
+                        }
+                        authorAssociation
+                    }
                 }
                 pageInfo {
                     endCursor
@@ -103,3 +174,69 @@ pub const TIMELINE_QUERY: &str = r#" query ($number: Int!, $after: String!, $tim
         }
     }
 } "#;
+
+/// The labels after the first page of `ISSUES_WITH_TIMELINE_QUERY`.
+pub const LABELS_QUERY: &str = r#" query ($owner: String = "rust-lang", $name: String = "rust", $number: Int!, $after: String!, $label_page_size: Int = 100) {
+    rateLimit {
+        cost
+    }
+    repository(owner: $owner, name: $name) {
+        issue(number: $number) {
+            labels(first: $label_page_size, after: $after) {
+                nodes {
+                    name
+                }
+                pageInfo {
+                    endCursor
+                    hasNextPage
+                }
+            }
+        }
+    }
+} "#;
+
+/// How many issues `ISSUES_WITH_TIMELINE_QUERY` would go through at most, and
+/// how much of the rate limit is left.
+pub const PLAN_QUERY: &str = r#" query ($owner: String = "rust-lang", $name: String = "rust", $states: [IssueState!], $filterBy: IssueFilters) {
+    rateLimit {
+        cost
+        limit
+        remaining
+        resetAt
+    }
+    repository(owner: $owner, name: $name) {
+        issues(states: $states, filterBy: $filterBy) {
+            totalCount
+        }
+    }
+} "#;
+
+/// The current state of an issue, right before mutating it.
+pub const CURRENT_ISSUE_QUERY: &str = r#" query ($owner: String = "rust-lang", $name: String = "rust", $number: Int!) {
+    repository(owner: $owner, name: $name) {
+        issue(number: $number) {
+            id
+            state
+            labels(first: 100) {
+                nodes {
+                    name
+                }
+                pageInfo {
+                    hasNextPage
+                }
+            }
+        }
+    }
+} "#;
+
+pub const ADD_COMMENT_MUTATION: &str = r#" mutation ($subjectId: ID!, $body: String!) {
+    addComment(input: { subjectId: $subjectId, body: $body }) {
+        clientMutationId
+    }
+} "#;
+
+pub const CLOSE_ISSUE_MUTATION: &str = r#" mutation ($issueId: ID!) {
+    closeIssue(input: { issueId: $issueId, stateReason: NOT_PLANNED }) {
+        clientMutationId
+    }
+} "#;
@@ -0,0 +1,305 @@
+//! Self-contained HTML reports with inline SVG charts, for `--report`. Nothing
+//! is loaded from the network, so reports can be viewed offline and shared as a
+//! single file.
+
+use std::fmt::Write;
+use std::path::Path;
+
+const CHART_HEIGHT: f64 = 200.0;
+const CHART_MARGIN: f64 = 40.0;
+/// Room for rotated bar labels.
+const CHART_BOTTOM_MARGIN: f64 = 80.0;
+const MAX_BAR_WIDTH: f64 = 48.0;
+/// Charts with many bars grow wider instead of letting the bars overlap.
+const MIN_BAR_WIDTH: f64 = 3.0;
+const MIN_CHART_WIDTH: f64 = 480.0;
+/// Bars are labeled only if there is room for it.
+const MIN_LABELED_BAR_WIDTH: f64 = 24.0;
+
+const STYLE: &str = "
+body { font-family: sans-serif; margin: 2em; color: #222; }
+svg { display: block; margin: 1em 0; }
+.bar { fill: #4c78a8; }
+.axis { stroke: #888; }
+text { font-size: 11px; }
+table { border-collapse: collapse; }
+th, td { border: 1px solid #ccc; padding: 0.2em 0.6em; text-align: left; }
+th { cursor: pointer; background: #eee; }
+";
+
+/// Sorts the table by the clicked column. Toggles between ascending and
+/// descending order. Cells with a `data-sort` attribute are sorted by it.
+const SORT_SCRIPT: &str = "
+document.querySelectorAll('table.sortable th').forEach((th, column) => {
+  th.addEventListener('click', () => {
+    const tbody = th.closest('table').tBodies[0];
+    const ascending = th.dataset.order !== 'asc';
+    th.dataset.order = ascending ? 'asc' : 'desc';
+    const key = row => {
+      const cell = row.cells[column];
+      const value = cell.dataset.sort ?? cell.textContent;
+      const number = Number(value);
+      return value !== '' && !isNaN(number) ? number : value;
+    };
+    const rows = Array.from(tbody.rows).sort((a, b) => {
+      const [x, y] = [key(a), key(b)];
+      return (x < y ? -1 : x > y ? 1 : 0) * (ascending ? 1 : -1);
+    });
+    rows.forEach(row => tbody.appendChild(row));
+  });
+});
+";
+
+pub struct HtmlReport {
+    title: String,
+    body: String,
+}
+
+pub enum Cell {
+    Text(String),
+    Number(f64),
+    /// Sorted by `sort` if given, e.g. the issue number for `#1234`, and by
+    /// `text` otherwise.
+    Link {
+        text: String,
+        url: String,
+        sort: Option<u32>,
+    },
+}
+
+impl HtmlReport {
+    pub fn new(title: &str) -> Self {
+        Self {
+            title: title.to_owned(),
+            body: String::new(),
+        }
+    }
+
+    pub fn add_heading(&mut self, text: &str) {
+        writeln!(self.body, "<h2>{}</h2>", escape(text)).unwrap();
+    }
+
+    pub fn add_paragraph(&mut self, text: &str) {
+        writeln!(self.body, "<p>{}</p>", escape(text)).unwrap();
+    }
+
+    pub fn add_bar_chart(&mut self, title: &str, bars: &[(String, f64)]) {
+        self.add_heading(title);
+
+        let max = bars.iter().map(|(_, value)| *value).fold(0.0, f64::max);
+        let bar_width =
+            (MIN_CHART_WIDTH / bars.len().max(1) as f64).clamp(MIN_BAR_WIDTH, MAX_BAR_WIDTH);
+        let width = (bar_width * bars.len() as f64).max(MIN_CHART_WIDTH) + 2.0 * CHART_MARGIN;
+        let height = CHART_MARGIN + CHART_HEIGHT + CHART_BOTTOM_MARGIN;
+        let baseline = CHART_MARGIN + CHART_HEIGHT;
+
+        writeln!(
+            self.body,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" role="img" aria-label="{}">"#,
+            escape(title)
+        )
+        .unwrap();
+        writeln!(
+            self.body,
+            r#"<line class="axis" x1="{CHART_MARGIN}" y1="{baseline}" x2="{}" y2="{baseline}"/>"#,
+            width - CHART_MARGIN
+        )
+        .unwrap();
+
+        for (index, (label, value)) in bars.iter().enumerate() {
+            let bar_height = if max > 0.0 {
+                value / max * CHART_HEIGHT
+            } else {
+                0.0
+            };
+            let x = CHART_MARGIN + index as f64 * bar_width;
+            let center = x + bar_width / 2.0;
+            writeln!(
+                self.body,
+                r#"<rect class="bar" x="{:.1}" y="{:.1}" width="{:.1}" height="{bar_height:.1}"><title>{}: {}</title></rect>"#,
+                x + 1.0,
+                baseline - bar_height,
+                (bar_width - 2.0).max(1.0),
+                escape(label),
+                format_number(*value),
+            )
+            .unwrap();
+            if bar_width >= MIN_LABELED_BAR_WIDTH {
+                writeln!(
+                    self.body,
+                    r#"<text x="{center:.1}" y="{:.1}" text-anchor="middle">{}</text>"#,
+                    baseline - bar_height - 4.0,
+                    format_number(*value)
+                )
+                .unwrap();
+                writeln!(
+                    self.body,
+                    r#"<text x="{center:.1}" y="{:.1}" text-anchor="end" transform="rotate(-45 {center:.1} {:.1})">{}</text>"#,
+                    baseline + 14.0,
+                    baseline + 14.0,
+                    escape(label)
+                )
+                .unwrap();
+            }
+        }
+
+        if let (Some((first, _)), Some((last, _))) = (bars.first(), bars.last()) {
+            if bar_width < MIN_LABELED_BAR_WIDTH {
+                writeln!(
+                    self.body,
+                    r#"<text x="{CHART_MARGIN}" y="{:.1}">{}</text><text x="{:.1}" y="{:.1}" text-anchor="end">{}</text>"#,
+                    baseline + 14.0,
+                    escape(first),
+                    width - CHART_MARGIN,
+                    baseline + 14.0,
+                    escape(last)
+                )
+                .unwrap();
+            }
+        }
+
+        writeln!(self.body, "</svg>").unwrap();
+    }
+
+    /// Adds a table that can be sorted by clicking on the column headers.
+    pub fn add_table(&mut self, title: &str, header: &[&str], rows: &[Vec<Cell>]) {
+        self.add_heading(title);
+
+        writeln!(self.body, r#"<table class="sortable"><thead><tr>"#).unwrap();
+        for column in header {
+            write!(self.body, "<th>{}</th>", escape(column)).unwrap();
+        }
+        writeln!(self.body, "</tr></thead><tbody>").unwrap();
+        for row in rows {
+            write!(self.body, "<tr>").unwrap();
+            for cell in row {
+                match cell {
+                    Cell::Text(text) => write!(self.body, "<td>{}</td>", escape(text)),
+                    Cell::Number(number) => write!(
+                        self.body,
+                        r#"<td data-sort="{number}">{}</td>"#,
+                        format_number(*number)
+                    ),
+                    Cell::Link { text, url, sort } => write!(
+                        self.body,
+                        r#"<td data-sort="{}"><a href="{}">{}</a></td>"#,
+                        sort.map_or_else(|| escape(text), |sort| sort.to_string()),
+                        escape(url),
+                        escape(text)
+                    ),
+                }
+                .unwrap();
+            }
+            writeln!(self.body, "</tr>").unwrap();
+        }
+        writeln!(self.body, "</tbody></table>").unwrap();
+    }
+
+    pub fn to_html(&self) -> String {
+        format!(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n<style>{STYLE}</style>\n</head>\n<body>\n<h1>{title}</h1>\n{}<script>{SORT_SCRIPT}</script>\n</body>\n</html>\n",
+            self.body,
+            title = escape(&self.title),
+        )
+    }
+
+    pub fn write_to(&self, path: &Path) -> std::io::Result<()> {
+        std::fs::write(path, self.to_html())
+    }
+}
+
+/// Counts durations in days into buckets suitable for a bar chart.
+pub fn day_histogram(days: &[f64]) -> Vec<(String, f64)> {
+    const BUCKETS: &[(&str, f64)] = &[
+        ("< 1 day", 1.0),
+        ("1-7 days", 7.0),
+        ("1-2 weeks", 14.0),
+        ("2-4 weeks", 28.0),
+        ("1-3 months", 91.0),
+        ("3-12 months", 365.0),
+        ("> 1 year", f64::INFINITY),
+    ];
+
+    let mut counts = vec![0.0; BUCKETS.len()];
+    for value in days {
+        let index = BUCKETS
+            .iter()
+            .position(|(_, upper)| value < upper)
+            .unwrap_or(BUCKETS.len() - 1);
+        counts[index] += 1.0;
+    }
+
+    BUCKETS
+        .iter()
+        .zip(counts)
+        .map(|((label, _), count)| (label.to_string(), count))
+        .collect()
+}
+
+fn format_number(number: f64) -> String {
+    if number.fract() == 0.0 {
+        format!("{number:.0}")
+    } else {
+        format!("{number:.1}")
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report() {
+        let mut report = HtmlReport::new("<Report>");
+        report.add_bar_chart("Weeks", &[("1".to_string(), 3.0), ("2".to_string(), 1.5)]);
+        report.add_table(
+            "Issues",
+            &["Issue", "Weeks"],
+            &[vec![
+                Cell::Link {
+                    text: "#1".to_string(),
+                    url: "https://github.com/rust-lang/rust/issues/1".to_string(),
+                    sort: Some(1),
+                },
+                Cell::Number(2.0),
+            ]],
+        );
+        let html = report.to_html();
+
+        assert!(html.contains("<title>&lt;Report&gt;</title>"));
+        assert!(html.contains("<title>1: 3</title>"));
+        assert!(html.contains("<title>2: 1.5</title>"));
+        assert!(html.contains(
+            r#"<td data-sort="1"><a href="https://github.com/rust-lang/rust/issues/1">#1</a></td>"#
+        ));
+        assert!(html.contains(r#"<td data-sort="2">2</td>"#));
+        // Self-contained.
+        assert!(!html.contains("src="));
+    }
+
+    #[test]
+    fn test_many_bars() {
+        let bars: Vec<_> = (0..1000).map(|i| (i.to_string(), 1.0)).collect();
+        let mut report = HtmlReport::new("Weeks");
+        report.add_bar_chart("Weeks", &bars);
+        let html = report.to_html();
+
+        assert!(html.contains(r#"<svg xmlns="http://www.w3.org/2000/svg" width="3080""#));
+        assert!(html.contains(r#"<rect class="bar" x="3038.0" y="40.0" width="1.0""#));
+    }
+
+    #[test]
+    fn test_day_histogram() {
+        let histogram = day_histogram(&[0.5, 3.0, 3.0, 400.0]);
+        assert_eq!(histogram[0], ("< 1 day".to_string(), 1.0));
+        assert_eq!(histogram[1], ("1-7 days".to_string(), 2.0));
+        assert_eq!(histogram[6], ("> 1 year".to_string(), 1.0));
+    }
+}
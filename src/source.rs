@@ -0,0 +1,256 @@
+//! Where issues come from: GitHub, or a JSONL data dump written by an earlier
+//! run. A dump can be analyzed any number of times without API requests.
+
+use anyhow::Context;
+use chrono::Utc;
+use serde::Deserialize;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+
+use crate::{GitHub, IssueWithTimelineItems, IssuesQuery, PagingReport, TimelineItem};
+
+pub enum IssueSource {
+    GitHub(Box<GitHub>),
+    /// One JSON object per line, either an issue or an object with the issue
+    /// in an `issue` field, like the `mcve-effectiveness` data dump.
+    Dump(PathBuf),
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum DumpLine {
+    Entry { issue: IssueWithTimelineItems },
+    Issue(IssueWithTimelineItems),
+}
+
+impl IssueSource {
+    /// Calls `issue_handler` for each issue that matches `query`, and reports
+    /// what went wrong while paging.
+    ///
+    /// For dumps, `states`, `filterBy.labels` and `timelineItemTypes` are
+    /// applied to the dumped data. `states` are as of
+    /// [`IssuesQuery::states_at`], and are refused for dumps without
+    /// `CLOSED_EVENT`s and `REOPENED_EVENT`s, in which all issues look open.
+    /// All matching issues are handled regardless of `pages`, and
+    /// `after_page_handler` is called once at the end.
+    pub async fn for_issues_with_timeline(
+        &self,
+        query: &IssuesQuery,
+        mut issue_handler: impl FnMut(&IssueWithTimelineItems),
+        mut after_page_handler: impl FnMut(),
+    ) -> anyhow::Result<PagingReport> {
+        match self {
+            IssueSource::GitHub(github) => {
+                github
+                    .for_issues_with_timeline(query, issue_handler, after_page_handler)
+                    .await
+            }
+            IssueSource::Dump(path) => {
+                if query.variables["states"].is_array() && !has_state_events(path)? {
+                    anyhow::bail!(
+                        "{} has no closed or reopened events, so it cannot tell which issues are open",
+                        path.display()
+                    );
+                }
+                for issue in read_dump(path)? {
+                    if let Some(issue) = apply_query(query, issue?) {
+                        issue_handler(&issue);
+                    }
+                }
+                after_page_handler();
+                Ok(PagingReport::default())
+            }
+        }
+    }
+}
+
+/// The issues in the dump at `path`.
+fn read_dump(
+    path: &Path,
+) -> anyhow::Result<impl Iterator<Item = anyhow::Result<IssueWithTimelineItems>> + '_> {
+    let reader = BufReader::new(
+        std::fs::File::open(path).with_context(|| format!("failed to open {}", path.display()))?,
+    );
+    Ok(reader.lines().enumerate().map(move |(index, line)| {
+        let (DumpLine::Entry { issue } | DumpLine::Issue(issue)) = serde_json::from_str(&line?)
+            .with_context(|| format!("{}:{}", path.display(), index + 1))?;
+        Ok(issue)
+    }))
+}
+
+/// Whether any issue in the dump at `path` was closed or reopened. Dumps of
+/// queries without these timeline items, like the `mcve-effectiveness` data
+/// dump, have none.
+fn has_state_events(path: &Path) -> anyhow::Result<bool> {
+    for issue in read_dump(path)? {
+        if issue?.timeline_items.iter().any(|item| {
+            matches!(
+                item,
+                TimelineItem::ClosedEvent { .. } | TimelineItem::ReopenedEvent { .. }
+            )
+        }) {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// Does what the GitHub API does with `query` for a dumped issue.
+pub(crate) fn apply_query(
+    query: &IssuesQuery,
+    mut issue: IssueWithTimelineItems,
+) -> Option<IssueWithTimelineItems> {
+    if !query.is_in_range(issue.created_at) {
+        return None;
+    }
+
+    if let Some(states) = query.variables["states"].as_array() {
+        let states_at = query.states_at.unwrap_or_else(|| Utc::now().fixed_offset());
+        let state = if issue.was_open_at(states_at) {
+            "OPEN"
+        } else {
+            "CLOSED"
+        };
+        if !states.iter().any(|s| s == state) {
+            return None;
+        }
+    }
+
+    if let Some(labels) = query.variables["filterBy"]["labels"].as_array() {
+        if !issue
+            .labels
+            .nodes
+            .iter()
+            .any(|label| labels.iter().any(|name| *name == label.name))
+        {
+            return None;
+        }
+    }
+
+    if let Some(item_types) = query.variables["timelineItemTypes"].as_array() {
+        issue
+            .timeline_items
+            .retain(|item| item_types.iter().any(|t| t == item.item_type()));
+    }
+
+    if query.is_excluded_as_anomalous(&issue) {
+        return None;
+    }
+
+    Some(issue)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::DateTime;
+    use std::str::FromStr;
+
+    use super::*;
+    use crate::test_support::{self, date};
+
+    /// Labeled with `label` when created, and closed right away if `closed`.
+    fn issue(created_at: &str, label: &str, closed: bool) -> IssueWithTimelineItems {
+        let mut timeline_items = vec![TimelineItem::LabeledEvent {
+            created_at: date(created_at),
+            label: test_support::label(label),
+            actor: None,
+        }];
+        if closed {
+            timeline_items.push(TimelineItem::ClosedEvent {
+                created_at: date(created_at),
+                actor: None,
+            });
+        }
+        IssueWithTimelineItems {
+            created_at: date(created_at),
+            ..test_support::issue(&[label], timeline_items)
+        }
+    }
+
+    #[test]
+    fn test_apply_query() {
+        let mut query = IssuesQuery::new(
+            serde_json::json!({
+                "states": ["OPEN"],
+                "filterBy": { "labels": ["E-needs-mcve"] },
+                "timelineItemTypes": ["CLOSED_EVENT"],
+            }),
+            1,
+        );
+        query.created_since = Some(DateTime::from_str("2020-01-01T00:00:00Z").unwrap());
+
+        let open = issue("2020-06-01T00:00:00Z", "E-needs-mcve", false);
+        assert_eq!(apply_query(&query, open).unwrap().timeline_items, vec![]);
+
+        let closed = issue("2020-06-01T00:00:00Z", "E-needs-mcve", true);
+        assert_eq!(apply_query(&query, closed), None);
+
+        let other_label = issue("2020-06-01T00:00:00Z", "C-bug", false);
+        assert_eq!(apply_query(&query, other_label), None);
+
+        let too_old = issue("2019-06-01T00:00:00Z", "E-needs-mcve", false);
+        assert_eq!(apply_query(&query, too_old), None);
+
+        // Still open as of before it was closed.
+        query.states_at = Some(DateTime::from_str("2020-07-01T00:00:00Z").unwrap());
+        let mut closed_later = issue("2020-06-01T00:00:00Z", "E-needs-mcve", false);
+        closed_later.timeline_items.push(TimelineItem::ClosedEvent {
+            created_at: DateTime::from_str("2020-08-01T00:00:00Z").unwrap(),
+            actor: None,
+        });
+        assert!(apply_query(&query, closed_later.clone()).is_some());
+        query.states_at = None;
+        assert_eq!(apply_query(&query, closed_later), None);
+    }
+
+    #[tokio::test]
+    async fn test_states_need_state_events() {
+        let path = std::env::temp_dir().join(format!(
+            "rust-issue-stats-states-{}.jsonl",
+            std::process::id()
+        ));
+        let open = issue("2020-06-01T00:00:00Z", "E-needs-mcve", false);
+        std::fs::write(&path, serde_json::to_string(&open).unwrap()).unwrap();
+        let source = IssueSource::Dump(path.clone());
+
+        let query = IssuesQuery::new(serde_json::json!({ "states": ["CLOSED"] }), 1);
+        let error = source
+            .for_issues_with_timeline(&query, |_| {}, || {})
+            .await
+            .unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("cannot tell which issues are open"));
+
+        let mut handled = 0;
+        let query = IssuesQuery::new(serde_json::json!({}), 1);
+        source
+            .for_issues_with_timeline(&query, |_| handled += 1, || {})
+            .await
+            .unwrap();
+        assert_eq!(handled, 1);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_apply_query_exclude_anomalous() {
+        let mut query = IssuesQuery::new(
+            serde_json::json!({ "timelineItemTypes": ["CLOSED_EVENT", "REOPENED_EVENT"] }),
+            1,
+        );
+        query.exclude_anomalous = true;
+
+        let mut closed_twice = issue("2020-06-01T00:00:00Z", "C-bug", true);
+        closed_twice
+            .timeline_items
+            .extend(closed_twice.timeline_items.last().cloned());
+        assert_eq!(apply_query(&query, closed_twice.clone()), None);
+
+        // The label without a labeled event does not count without label events.
+        let closed_once = issue("2020-06-01T00:00:00Z", "C-bug", true);
+        assert!(apply_query(&query, closed_once).is_some());
+
+        query.exclude_anomalous = false;
+        assert!(apply_query(&query, closed_twice).is_some());
+    }
+}
@@ -0,0 +1,238 @@
+use chrono::{DateTime, FixedOffset};
+use serde::{Serialize, Serializer};
+use std::fmt::{Display, Formatter};
+
+use crate::IssueWithTimelineItems;
+
+/// Summary of a distribution of durations, in days. The [`Default`] is the
+/// summary of no values, with `NaN` (`null` in JSON) for everything but the
+/// count.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Distribution {
+    pub count: usize,
+    pub min: f64,
+    pub p25: f64,
+    pub median: f64,
+    pub p75: f64,
+    pub p90: f64,
+    pub max: f64,
+    pub mean: f64,
+}
+
+/// Result of a two-sided Mann-Whitney U test, using the normal approximation
+/// with tie correction. The [`Default`] has `NaN` (`null` in JSON) everywhere
+/// and represents a test that could not be made.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MannWhitneyU {
+    /// The U statistic of the first sample.
+    pub u: f64,
+    pub z: f64,
+    pub p_value: f64,
+    /// Probability that a random value from the first sample is larger than a
+    /// random value from the second sample (ties count as half).
+    pub effect_size: f64,
+}
+
+impl Default for Distribution {
+    fn default() -> Self {
+        Self {
+            count: 0,
+            min: f64::NAN,
+            p25: f64::NAN,
+            median: f64::NAN,
+            p75: f64::NAN,
+            p90: f64::NAN,
+            max: f64::NAN,
+            mean: f64::NAN,
+        }
+    }
+}
+
+impl Default for MannWhitneyU {
+    fn default() -> Self {
+        Self {
+            u: f64::NAN,
+            z: f64::NAN,
+            p_value: f64::NAN,
+            effect_size: f64::NAN,
+        }
+    }
+}
+
+/// For `#[serde(serialize_with = "...")]` on fields with raw values that should
+/// be output as a [`Distribution`].
+pub fn serialize_as_distribution<S: Serializer>(
+    values: &[f64],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    Distribution::from_values(values)
+        .unwrap_or_default()
+        .serialize(serializer)
+}
+
+/// Days, with fractions, from the creation of `issue` until `date`.
+pub fn days_since(issue: &IssueWithTimelineItems, date: &DateTime<FixedOffset>) -> f64 {
+    date.signed_duration_since(issue.created_at).num_seconds() as f64 / 86400.0
+}
+
+/// Writes `what` and the [`Distribution`] of `days` as a line, for the
+/// `Display` impls of reports.
+pub fn write_distribution(f: &mut Formatter<'_>, what: &str, days: &[f64]) -> std::fmt::Result {
+    let distribution = Distribution::from_values(days).unwrap_or_default();
+    writeln!(f, "{what}: {distribution}")
+}
+
+impl Distribution {
+    /// Returns `None` if there are no values.
+    pub fn from_values(values: &[f64]) -> Option<Self> {
+        if values.is_empty() {
+            return None;
+        }
+
+        let mut sorted = values.to_vec();
+        sorted.sort_by(f64::total_cmp);
+
+        Some(Self {
+            count: sorted.len(),
+            min: sorted[0],
+            p25: quantile(&sorted, 0.25),
+            median: quantile(&sorted, 0.5),
+            p75: quantile(&sorted, 0.75),
+            p90: quantile(&sorted, 0.9),
+            max: sorted[sorted.len() - 1],
+            mean: sorted.iter().sum::<f64>() / sorted.len() as f64,
+        })
+    }
+}
+
+/// Linear interpolation between closest ranks. `sorted` must be non-empty.
+fn quantile(sorted: &[f64], q: f64) -> f64 {
+    let pos = q * (sorted.len() - 1) as f64;
+    let lower = pos.floor() as usize;
+    let upper = pos.ceil() as usize;
+    sorted[lower] + (sorted[upper] - sorted[lower]) * (pos - lower as f64)
+}
+
+/// Returns `None` if any of the samples is empty or if all values are equal.
+pub fn mann_whitney_u(xs: &[f64], ys: &[f64]) -> Option<MannWhitneyU> {
+    if xs.is_empty() || ys.is_empty() {
+        return None;
+    }
+
+    let mut combined: Vec<(f64, bool)> = xs
+        .iter()
+        .map(|x| (*x, true))
+        .chain(ys.iter().map(|y| (*y, false)))
+        .collect();
+    combined.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    // Assign average ranks to ties and collect the tie correction term.
+    let n = combined.len();
+    let mut rank_sum_x = 0.0;
+    let mut tie_term = 0.0;
+    let mut i = 0;
+    while i < n {
+        let mut j = i;
+        while j + 1 < n && combined[j + 1].0 == combined[i].0 {
+            j += 1;
+        }
+        let ties = (j - i + 1) as f64;
+        let average_rank = (i + j) as f64 / 2.0 + 1.0;
+        rank_sum_x += average_rank * combined[i..=j].iter().filter(|(_, x)| *x).count() as f64;
+        tie_term += ties * ties * ties - ties;
+        i = j + 1;
+    }
+
+    let n1 = xs.len() as f64;
+    let n2 = ys.len() as f64;
+    let n = n as f64;
+    let u = rank_sum_x - n1 * (n1 + 1.0) / 2.0;
+    let mean = n1 * n2 / 2.0;
+    let variance = n1 * n2 / 12.0 * ((n + 1.0) - tie_term / (n * (n - 1.0)));
+    if variance <= 0.0 {
+        return None;
+    }
+
+    // Continuity correction towards the mean.
+    let diff = u - mean;
+    let z = (diff - 0.5 * diff.signum()) / variance.sqrt();
+
+    Some(MannWhitneyU {
+        u,
+        z,
+        p_value: (2.0 * (1.0 - standard_normal_cdf(z.abs()))).clamp(0.0, 1.0),
+        effect_size: u / (n1 * n2),
+    })
+}
+
+fn standard_normal_cdf(z: f64) -> f64 {
+    0.5 * (1.0 + erf(z / std::f64::consts::SQRT_2))
+}
+
+/// Abramowitz and Stegun formula 7.1.26. Maximum error is 1.5e-7.
+fn erf(x: f64) -> f64 {
+    let t = 1.0 / (1.0 + 0.3275911 * x.abs());
+    let poly = t
+        * (0.254829592
+            + t * (-0.284496736 + t * (1.421413741 + t * (-1.453152027 + t * 1.061405429))));
+    let y = 1.0 - poly * (-x * x).exp();
+    y.copysign(x)
+}
+
+impl Display for Distribution {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.count == 0 {
+            return f.write_str("no data");
+        }
+        write!(
+            f,
+            "n={} min={:.1} p25={:.1} median={:.1} p75={:.1} p90={:.1} max={:.1} mean={:.1}",
+            self.count, self.min, self.p25, self.median, self.p75, self.p90, self.max, self.mean
+        )
+    }
+}
+
+impl Display for MannWhitneyU {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.u.is_nan() {
+            return f.write_str("not enough data");
+        }
+        write!(
+            f,
+            "U={:.1} z={:.3} p={:.4} P(first > second)={:.3}",
+            self.u, self.z, self.p_value, self.effect_size
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_distribution() {
+        let distribution = Distribution::from_values(&[4.0, 1.0, 3.0, 2.0, 5.0]).unwrap();
+        assert_eq!(distribution.count, 5);
+        assert_eq!(distribution.min, 1.0);
+        assert_eq!(distribution.p25, 2.0);
+        assert_eq!(distribution.median, 3.0);
+        assert_eq!(distribution.max, 5.0);
+        assert_eq!(distribution.mean, 3.0);
+
+        assert_eq!(Distribution::from_values(&[]), None);
+    }
+
+    #[test]
+    fn test_mann_whitney_u() {
+        // Tie and continuity corrected, like
+        // scipy.stats.mannwhitneyu(xs, ys, method="asymptotic")
+        let xs = [1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0];
+        let ys = [5.0, 7.0, 9.0, 10.0, 11.0, 12.0, 13.0, 14.0, 15.0];
+        let result = mann_whitney_u(&xs, &ys).unwrap();
+        assert_eq!(result.u, 5.0);
+        assert!((result.p_value - 0.0033).abs() < 0.0001, "{result}");
+
+        assert_eq!(mann_whitney_u(&[1.0], &[]), None);
+        assert_eq!(mann_whitney_u(&[1.0, 1.0], &[1.0]), None);
+    }
+}
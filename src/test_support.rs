@@ -0,0 +1,29 @@
+//! Factories for the issues of unit tests.
+
+use chrono::{DateTime, FixedOffset};
+use std::str::FromStr;
+
+use crate::{IssueWithTimelineItems, Label, Labels, TimelineItem};
+
+pub(crate) fn date(s: &str) -> DateTime<FixedOffset> {
+    DateTime::from_str(s).unwrap()
+}
+
+pub(crate) fn label(name: &str) -> Label {
+    Label {
+        name: name.to_string(),
+    }
+}
+
+/// Issue #42, created on 2020-01-01, with the given current labels.
+pub(crate) fn issue(labels: &[&str], timeline_items: Vec<TimelineItem>) -> IssueWithTimelineItems {
+    IssueWithTimelineItems {
+        url: "https://github.com/rust-lang/rust/issues/42".to_string(),
+        number: 42,
+        title: "Test issue".to_string(),
+        author: None,
+        labels: Labels::new(labels.iter().map(|name| label(name)).collect()),
+        created_at: date("2020-01-01T00:00:00Z"),
+        timeline_items,
+    }
+}
@@ -0,0 +1,320 @@
+//! Data-quality checks of timelines. Real timelines contain oddities that the
+//! analyses have to work around, such as labels that were removed without
+//! having been added, or issues that were closed twice.
+
+use chrono::{DateTime, FixedOffset};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+
+use crate::{IssueWithTimelineItems, LabelInconsistency, TimelineItem};
+
+/// Something in the timeline of an issue that should not happen.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Anomaly {
+    Label(LabelInconsistency),
+    /// The issue was closed while already closed.
+    ClosedTwice {
+        at: DateTime<FixedOffset>,
+    },
+    /// The issue was reopened while open.
+    ReopenedWhileOpen {
+        at: DateTime<FixedOffset>,
+    },
+    /// A timeline item is older than the issue, e.g. for issues that were
+    /// transferred from another repository.
+    BeforeCreation {
+        item_type: &'static str,
+        at: DateTime<FixedOffset>,
+    },
+}
+
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum AnomalyCategory {
+    UnlabeledWithoutLabel,
+    LabeledTwice,
+    MissingLabeledEvent,
+    /// Labels that were deleted or renamed after they were added.
+    DeletedOrRenamedLabel,
+    ClosedTwice,
+    ReopenedWhileOpen,
+    BeforeCreation,
+}
+
+/// An [`Anomaly`] of an issue, for listings.
+#[derive(Debug, PartialEq, Eq, Clone, Serialize)]
+pub struct AnomalyListing {
+    pub category: AnomalyCategory,
+    pub url: String,
+    pub anomaly: String,
+}
+
+/// The anomalies of a collection of issues by category.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Serialize)]
+pub struct ValidationReport {
+    pub issues: usize,
+    pub anomalous_issues: usize,
+    pub by_category: BTreeMap<AnomalyCategory, Vec<AnomalyListing>>,
+}
+
+impl Anomaly {
+    pub fn category(&self) -> AnomalyCategory {
+        match self {
+            Anomaly::Label(LabelInconsistency::UnlabeledWithoutLabel { .. }) => {
+                AnomalyCategory::UnlabeledWithoutLabel
+            }
+            Anomaly::Label(LabelInconsistency::LabeledTwice { .. }) => {
+                AnomalyCategory::LabeledTwice
+            }
+            Anomaly::Label(LabelInconsistency::MissingLabeledEvent { .. }) => {
+                AnomalyCategory::MissingLabeledEvent
+            }
+            Anomaly::Label(LabelInconsistency::MissingUnlabeledEvent { .. }) => {
+                AnomalyCategory::DeletedOrRenamedLabel
+            }
+            Anomaly::ClosedTwice { .. } => AnomalyCategory::ClosedTwice,
+            Anomaly::ReopenedWhileOpen { .. } => AnomalyCategory::ReopenedWhileOpen,
+            Anomaly::BeforeCreation { .. } => AnomalyCategory::BeforeCreation,
+        }
+    }
+}
+
+impl AnomalyCategory {
+    /// The `timelineItemTypes` without which every issue would look like it
+    /// has anomalies of this category, e.g. labels that were never added if
+    /// `LABELED_EVENT`s were not queried.
+    pub fn item_types(self) -> &'static [&'static str] {
+        match self {
+            AnomalyCategory::UnlabeledWithoutLabel
+            | AnomalyCategory::LabeledTwice
+            | AnomalyCategory::MissingLabeledEvent
+            | AnomalyCategory::DeletedOrRenamedLabel => &["LABELED_EVENT", "UNLABELED_EVENT"],
+            AnomalyCategory::ClosedTwice | AnomalyCategory::ReopenedWhileOpen => {
+                &["CLOSED_EVENT", "REOPENED_EVENT"]
+            }
+            AnomalyCategory::BeforeCreation => &[],
+        }
+    }
+}
+
+impl IssueWithTimelineItems {
+    /// The anomalies in the timeline. Only meaningful for timelines with all
+    /// of [`AnomalyCategory::item_types`] of a category.
+    pub fn anomalies(&self) -> Vec<Anomaly> {
+        let mut anomalies: Vec<_> = self
+            .label_history()
+            .inconsistencies
+            .into_iter()
+            .map(Anomaly::Label)
+            .collect();
+
+        let mut open = true;
+        for item in self.chronological_timeline_items() {
+            if item.created_at() < self.created_at {
+                anomalies.push(Anomaly::BeforeCreation {
+                    item_type: item.item_type(),
+                    at: item.created_at(),
+                });
+            }
+            match item {
+                TimelineItem::ClosedEvent { created_at, .. } => {
+                    if !open {
+                        anomalies.push(Anomaly::ClosedTwice { at: *created_at });
+                    }
+                    open = false;
+                }
+                TimelineItem::ReopenedEvent { created_at, .. } => {
+                    if open {
+                        anomalies.push(Anomaly::ReopenedWhileOpen { at: *created_at });
+                    }
+                    open = true;
+                }
+                _ => {}
+            }
+        }
+
+        anomalies
+    }
+
+    /// Whether the timeline has anomalies that can be told from timelines with
+    /// `item_types`.
+    pub fn has_anomalies(&self, item_types: &[&str]) -> bool {
+        self.anomalies().iter().any(|anomaly| {
+            anomaly
+                .category()
+                .item_types()
+                .iter()
+                .all(|item_type| item_types.contains(item_type))
+        })
+    }
+}
+
+impl ValidationReport {
+    pub fn add(&mut self, issue: &IssueWithTimelineItems) {
+        self.issues += 1;
+        let anomalies = issue.anomalies();
+        if anomalies.is_empty() {
+            return;
+        }
+        self.anomalous_issues += 1;
+        for anomaly in anomalies {
+            self.by_category
+                .entry(anomaly.category())
+                .or_default()
+                .push(AnomalyListing {
+                    category: anomaly.category(),
+                    url: issue.url.clone(),
+                    anomaly: anomaly.to_string(),
+                });
+        }
+    }
+}
+
+/// Checks the timelines of `issues`, which should contain all
+/// [`TIMELINE_ITEM_TYPES`](crate::TIMELINE_ITEM_TYPES).
+pub fn validate<'a>(
+    issues: impl IntoIterator<Item = &'a IssueWithTimelineItems>,
+) -> ValidationReport {
+    let mut report = ValidationReport::default();
+    for issue in issues {
+        report.add(issue);
+    }
+    report
+}
+
+impl Display for Anomaly {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Anomaly::Label(inconsistency) => write!(f, "{inconsistency}"),
+            Anomaly::ClosedTwice { at } => {
+                write!(f, "closed {} while closed", at.format("%Y-%m-%d"))
+            }
+            Anomaly::ReopenedWhileOpen { at } => {
+                write!(f, "reopened {} while open", at.format("%Y-%m-%d"))
+            }
+            Anomaly::BeforeCreation { item_type, at } => write!(
+                f,
+                "{item_type} {} before the issue was created",
+                at.format("%Y-%m-%d")
+            ),
+        }
+    }
+}
+
+impl Display for AnomalyCategory {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let value = clap::ValueEnum::to_possible_value(self).expect("no skipped variants");
+        write!(f, "{}", value.get_name())
+    }
+}
+
+impl Display for AnomalyListing {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}: {}", self.url, self.category, self.anomaly)
+    }
+}
+
+impl Display for ValidationReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} of {} issues have anomalies",
+            self.anomalous_issues, self.issues
+        )?;
+        for (category, listings) in &self.by_category {
+            write!(f, "\n  {category}: {}", listings.len())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{date, issue, label};
+
+    fn labeled(name: &str, at: &str) -> TimelineItem {
+        TimelineItem::LabeledEvent {
+            created_at: date(at),
+            label: label(name),
+            actor: None,
+        }
+    }
+
+    fn closed(at: &str) -> TimelineItem {
+        TimelineItem::ClosedEvent {
+            created_at: date(at),
+            actor: None,
+        }
+    }
+
+    #[test]
+    fn test_anomalies() {
+        let clean = issue(
+            &["C-bug"],
+            vec![
+                labeled("C-bug", "2020-01-02T00:00:00Z"),
+                closed("2020-02-01T00:00:00Z"),
+            ],
+        );
+        assert_eq!(clean.anomalies(), vec![]);
+
+        let odd = issue(
+            &[],
+            vec![
+                labeled("A-old-name", "2019-12-01T00:00:00Z"),
+                closed("2020-02-01T00:00:00Z"),
+                closed("2020-03-01T00:00:00Z"),
+            ],
+        );
+        let categories: Vec<_> = odd.anomalies().iter().map(Anomaly::category).collect();
+        assert_eq!(
+            categories,
+            [
+                AnomalyCategory::DeletedOrRenamedLabel,
+                AnomalyCategory::BeforeCreation,
+                AnomalyCategory::ClosedTwice,
+            ]
+        );
+        assert!(odd.has_anomalies(&["CLOSED_EVENT", "REOPENED_EVENT"]));
+        assert!(!issue(
+            &[],
+            vec![
+                closed("2020-02-01T00:00:00Z"),
+                closed("2020-03-01T00:00:00Z")
+            ]
+        )
+        .has_anomalies(&["CLOSED_EVENT"]));
+        // Without label events, any label looks like it was never added.
+        assert!(!issue(&["C-bug"], vec![]).has_anomalies(&["CLOSED_EVENT", "REOPENED_EVENT"]));
+    }
+
+    #[test]
+    fn test_validate() {
+        let issues = [
+            issue(&[], vec![]),
+            issue(
+                &[],
+                vec![
+                    closed("2020-02-01T00:00:00Z"),
+                    closed("2020-03-01T00:00:00Z"),
+                ],
+            ),
+        ];
+        let report = validate(&issues);
+        assert_eq!((report.issues, report.anomalous_issues), (2, 1));
+        assert_eq!(
+            report.by_category[&AnomalyCategory::ClosedTwice],
+            [AnomalyListing {
+                category: AnomalyCategory::ClosedTwice,
+                url: "https://github.com/rust-lang/rust/issues/42".to_owned(),
+                anomaly: "closed 2020-03-01 while closed".to_owned(),
+            }]
+        );
+        assert_eq!(
+            report.to_string(),
+            "1 of 2 issues have anomalies\n  closed-twice: 1"
+        );
+    }
+}
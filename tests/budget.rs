@@ -0,0 +1,66 @@
+mod mock;
+
+use mock::{handled, query, MockGitHub};
+use rust_issue_stats::*;
+
+#[tokio::test]
+async fn test_plan() {
+    let server = MockGitHub::from_fixture("issues.json").start();
+
+    let plan = server.github().plan(&query(10)).await.unwrap();
+    assert_eq!(plan.matching_issues, 5);
+    assert_eq!((plan.pages, plan.requests, plan.cost), (3, 3, 3));
+    assert_eq!(plan.rate_limit_remaining, 4999);
+
+    let requests = server.requests();
+    assert_eq!(requests.len(), 1);
+    // Only the variables that `PLAN_QUERY` declares.
+    assert_eq!(requests[0]["variables"], serde_json::json!({}));
+}
+
+#[tokio::test]
+async fn test_max_requests() {
+    let server = MockGitHub::from_fixture("issues.json").start();
+    let github = server.github_builder().max_requests(2).build().unwrap();
+
+    // The rest of the timeline of issue 2 is not fetched, so the second page
+    // is incomplete.
+    assert_eq!(handled(&github, &query(10)).await.unwrap(), "45||");
+    assert_eq!(server.requests().len(), 2);
+    assert_eq!(
+        github.api_usage(),
+        ApiUsage {
+            requests: 2,
+            cost: 2
+        }
+    );
+}
+
+#[tokio::test]
+async fn test_max_cost() {
+    let server = MockGitHub::from_fixture("issues.json").start();
+    let github = server.github_builder().max_cost(3).build().unwrap();
+
+    assert_eq!(handled(&github, &query(10)).await.unwrap(), "45|23|");
+    assert_eq!(server.requests().len(), 3);
+}
+
+#[tokio::test]
+async fn test_cached_responses_are_free() {
+    let cache_dir =
+        std::env::temp_dir().join(format!("rust-issue-stats-budget-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&cache_dir);
+
+    let server = MockGitHub::from_fixture("issues.json").start();
+    let github = server
+        .github_builder()
+        .max_requests(1)
+        .build()
+        .unwrap()
+        .with_cache_dir(cache_dir.clone());
+    assert_eq!(handled(&github, &query(1)).await.unwrap(), "45|");
+    assert_eq!(handled(&github, &query(1)).await.unwrap(), "45|");
+    assert_eq!(github.api_usage().requests, 1);
+
+    std::fs::remove_dir_all(&cache_dir).unwrap();
+}
@@ -0,0 +1,200 @@
+mod mock;
+
+use chrono::DateTime;
+use clap::Parser;
+use mock::{MockServer, Response};
+use rust_issue_stats::cli::Cli;
+use rust_issue_stats::*;
+
+fn issue() -> IssueWithTimelineItems {
+    IssueWithTimelineItems {
+        url: "https://github.com/rust-lang/rust/issues/1".to_string(),
+        number: 1,
+        title: "Stale".to_string(),
+        author: None,
+        labels: Labels::new(vec![]),
+        created_at: DateTime::parse_from_rfc3339("2019-01-01T00:00:00Z").unwrap(),
+        timeline_items: vec![],
+    }
+}
+
+fn actions() -> TriageActions {
+    TriageActions {
+        comment: Some("Closing {url}".to_string()),
+        close: true,
+    }
+}
+
+fn values() -> Vec<(&'static str, String)> {
+    vec![("url", issue().url)]
+}
+
+/// The issue as GitHub returns it right before mutating it.
+fn current_issue(state: &str, labels: &[&str]) -> serde_json::Value {
+    let labels: Vec<_> = labels
+        .iter()
+        .map(|name| serde_json::json!({ "name": name }))
+        .collect();
+    serde_json::json!({
+        "id": "I_1",
+        "state": state,
+        "labels": { "nodes": labels, "pageInfo": { "hasNextPage": false } },
+    })
+}
+
+/// Answers like GitHub with `issue` as the current issue, but fails
+/// `closeIssue` if `fail_close`.
+fn respond(
+    issue: serde_json::Value,
+    fail_close: bool,
+) -> impl FnMut(&serde_json::Value) -> Response {
+    move |request| {
+        let query = request["query"].as_str().unwrap();
+        let body = if query.contains("issue(number: $number)") {
+            serde_json::json!({ "data": { "repository": { "issue": issue } } })
+        } else if query.contains("addComment") {
+            serde_json::json!({ "data": { "addComment": { "clientMutationId": null } } })
+        } else if query.contains("closeIssue") && !fail_close {
+            serde_json::json!({ "data": { "closeIssue": { "clientMutationId": null } } })
+        } else {
+            serde_json::json!({ "data": null, "errors": [{ "message": "Resource not accessible by integration" }] })
+        };
+        body.into()
+    }
+}
+
+fn audit_log(test: &str) -> std::path::PathBuf {
+    let path = std::env::temp_dir().join(format!(
+        "rust-issue-stats-{test}-{}.jsonl",
+        std::process::id()
+    ));
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+fn read_audit_log(path: &std::path::Path) -> Vec<serde_json::Value> {
+    std::fs::read_to_string(path)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect()
+}
+
+#[tokio::test]
+async fn test_dry_run_makes_no_requests() {
+    let server = MockServer::start(respond(current_issue("OPEN", &[E_NEEDS_MCVE]), false));
+    let mut triage = BulkTriage::dry_run("rust-lang", "rust", actions(), 10);
+
+    assert!(triage.handle(&issue(), &values()).await.unwrap());
+    assert_eq!(server.requests(), Vec::<serde_json::Value>::new());
+}
+
+#[tokio::test]
+async fn test_apply() {
+    let server = MockServer::start(respond(current_issue("OPEN", &[E_NEEDS_MCVE]), false));
+    let path = audit_log("apply");
+    let mut triage = BulkTriage::dry_run("rust-lang", "rust", actions(), 1)
+        .with_required_label(E_NEEDS_MCVE)
+        .with_apply(server.github(), path.clone());
+
+    assert!(triage.handle(&issue(), &values()).await.unwrap());
+    // The cap is reached.
+    assert!(!triage.handle(&issue(), &values()).await.unwrap());
+
+    let requests = server.requests();
+    assert_eq!(requests.len(), 3);
+    assert_eq!(
+        requests[0]["variables"],
+        serde_json::json!({ "owner": "rust-lang", "name": "rust", "number": 1 })
+    );
+    assert_eq!(
+        requests[1]["variables"],
+        serde_json::json!({ "subjectId": "I_1", "body": "Closing https://github.com/rust-lang/rust/issues/1" })
+    );
+    assert!(requests[2]["query"]
+        .as_str()
+        .unwrap()
+        .contains("stateReason: NOT_PLANNED"));
+    assert_eq!(
+        requests[2]["variables"],
+        serde_json::json!({ "issueId": "I_1" })
+    );
+
+    let audit = read_audit_log(&path);
+    assert_eq!(audit.len(), 2);
+    assert_eq!(audit[0]["mutation"], "addComment");
+    assert_eq!(audit[1]["mutation"], "closeIssue");
+    assert!(audit.iter().all(|entry| entry["result"] == "ok"));
+}
+
+#[tokio::test]
+async fn test_failed_mutation_stops_and_is_audited() {
+    let server = MockServer::start(respond(current_issue("OPEN", &[]), true));
+    let path = audit_log("failed");
+    let mut triage = BulkTriage::dry_run("rust-lang", "rust", actions(), 10)
+        .with_apply(server.github(), path.clone());
+
+    let error = triage.handle(&issue(), &values()).await.unwrap_err();
+    assert!(format!("{error:#}").contains("Resource not accessible"));
+
+    let audit = read_audit_log(&path);
+    assert_eq!(audit.len(), 2);
+    assert_eq!(audit[0]["result"], "ok");
+    assert!(audit[1]["result"]
+        .as_str()
+        .unwrap()
+        .contains("Resource not accessible"));
+}
+
+#[tokio::test]
+async fn test_apply_skips_changed_issues() {
+    for (state, labels) in [("CLOSED", [E_NEEDS_MCVE]), ("OPEN", ["C-bug"])] {
+        let server = MockServer::start(respond(current_issue(state, &labels), false));
+        let path = audit_log(&format!("changed-{state}"));
+        let mut triage = BulkTriage::dry_run("rust-lang", "rust", actions(), 1)
+            .with_required_label(E_NEEDS_MCVE)
+            .with_apply(server.github(), path.clone());
+
+        // Skipped issues do not count towards the cap.
+        assert!(triage.handle(&issue(), &values()).await.unwrap());
+        assert!(triage.handle(&issue(), &values()).await.unwrap());
+
+        // Only the lookups, no mutations.
+        let requests = server.requests();
+        assert_eq!(requests.len(), 2);
+        assert!(requests
+            .iter()
+            .all(|request| !request["query"].as_str().unwrap().contains("mutation")));
+        assert!(!path.exists());
+    }
+}
+
+#[tokio::test]
+async fn test_apply_refuses_stale_data() {
+    let server = MockServer::start(respond(current_issue("OPEN", &[E_NEEDS_MCVE]), false));
+    let dump = std::env::temp_dir().join("rust-issue-stats-stale.jsonl");
+    let dump = dump.to_str().unwrap();
+    let cache = std::env::temp_dir().join("rust-issue-stats-stale-cache");
+    let cache = cache.to_str().unwrap();
+
+    for stale_data in [["--cache", cache], ["--source", dump]] {
+        let cli = Cli::try_parse_from(
+            [
+                "rust-issue-stats",
+                "--api-url",
+                server.base_url(),
+                "--token",
+                "mock-token",
+                "old-mcve",
+                "--close",
+                "--apply",
+            ]
+            .into_iter()
+            .chain(stale_data),
+        )
+        .unwrap();
+        let error = cli.command.run(&cli.global).await.unwrap_err();
+        assert!(error.to_string().contains("--apply"), "{error:#}");
+    }
+    assert_eq!(server.requests(), Vec::<serde_json::Value>::new());
+}
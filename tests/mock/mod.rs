@@ -0,0 +1,420 @@
+//! A GitHub GraphQL API on localhost for integration tests. [`MockServer`]
+//! answers each request with whatever a function returns and records the
+//! requests, and [`MockGitHub`] is such a function that serves issues from a
+//! fixture file like GitHub would.
+
+#![allow(dead_code)] // Not every test uses everything.
+
+use rust_issue_stats::{GitHub, GitHubBuilder, IssuesQuery, TIMELINE_ITEM_TYPES};
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+
+pub struct MockServer {
+    base_url: String,
+    requests: Arc<Mutex<Vec<serde_json::Value>>>,
+    headers: Arc<Mutex<Vec<Headers>>>,
+}
+
+/// The names and values of the headers of a request.
+type Headers = Vec<(String, String)>;
+
+#[derive(Debug, Clone)]
+pub struct Response {
+    pub status: u16,
+    pub body: serde_json::Value,
+}
+
+impl From<serde_json::Value> for Response {
+    fn from(body: serde_json::Value) -> Self {
+        Self { status: 200, body }
+    }
+}
+
+impl MockServer {
+    /// `respond` gets the JSON body of each request, i.e. `query` and
+    /// `variables`, and returns the response.
+    pub fn start(mut respond: impl FnMut(&serde_json::Value) -> Response + Send + 'static) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(vec![]));
+        let headers = Arc::new(Mutex::new(vec![]));
+
+        let (recorded, recorded_headers) = (requests.clone(), headers.clone());
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(stream) = stream else {
+                    continue;
+                };
+                if let Some((headers, request)) = read_request(&stream) {
+                    let response = respond(&request);
+                    recorded.lock().unwrap().push(request);
+                    recorded_headers.lock().unwrap().push(headers);
+                    write_response(&stream, &response);
+                }
+            }
+        });
+
+        Self {
+            base_url,
+            requests,
+            headers,
+        }
+    }
+
+    /// A client that sends its requests to this server.
+    pub fn github(&self) -> GitHub {
+        self.github_builder().build().unwrap()
+    }
+
+    /// A builder for a client that sends its requests to this server.
+    pub fn github_builder(&self) -> GitHubBuilder {
+        GitHub::builder()
+            .token("mock-token".to_owned())
+            .base_url(self.base_url.clone())
+    }
+
+    /// What `--api-url` should be to send requests to this server.
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// The requests so far, oldest first.
+    pub fn requests(&self) -> Vec<serde_json::Value> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    /// The values of the header `name` in each request so far, oldest first.
+    pub fn header_values(&self, name: &str) -> Vec<Vec<String>> {
+        self.headers
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|headers| {
+                headers
+                    .iter()
+                    .filter(|(n, _)| n.eq_ignore_ascii_case(name))
+                    .map(|(_, value)| value.clone())
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+/// Serves `ISSUES_WITH_TIMELINE_QUERY`, `TIMELINE_QUERY`, `LABELS_QUERY` and
+/// `PLAN_QUERY`, where every request costs 1 point, from issues in the format
+/// of a data dump, i.e. with all timeline items in an array, sorted by
+/// `createdAt`. Cursors are indexes, e.g. `issue-3`, `item-7` and `label-1`.
+pub struct MockGitHub {
+    issues: Vec<serde_json::Value>,
+    /// Responses to use instead of the normal one for the n-th request,
+    /// counting from 0.
+    overrides: HashMap<usize, Response>,
+    /// Issues that are `null` on pages of issues, with an error.
+    unavailable: Vec<u64>,
+    /// Issues that are deleted before the n-th request.
+    deletions: Vec<(usize, u64)>,
+    handled_requests: usize,
+}
+
+impl MockGitHub {
+    /// Reads `tests/fixtures/{name}`, a JSON array of issues.
+    pub fn from_fixture(name: &str) -> Self {
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures")
+            .join(name);
+        let issues = serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+        Self {
+            issues,
+            overrides: HashMap::new(),
+            unavailable: vec![],
+            deletions: vec![],
+            handled_requests: 0,
+        }
+    }
+
+    /// Answers the n-th request with `response` instead.
+    pub fn with_response(mut self, request_index: usize, response: Response) -> Self {
+        self.overrides.insert(request_index, response);
+        self
+    }
+
+    /// Answers the n-th request with a GraphQL error.
+    pub fn with_error(self, request_index: usize, message: &str) -> Self {
+        self.with_response(
+            request_index,
+            serde_json::json!({
+                "data": null,
+                "errors": [{ "message": message }],
+            })
+            .into(),
+        )
+    }
+
+    /// Answers the n-th request like GitHub does when the primary rate limit
+    /// is exceeded.
+    pub fn with_rate_limit(self, request_index: usize) -> Self {
+        self.with_response(
+            request_index,
+            Response {
+                status: 403,
+                body: serde_json::json!({
+                    "message": "API rate limit exceeded for user ID 1.",
+                    "documentation_url": "https://docs.github.com/rest/overview/resources-in-the-rest-api#rate-limiting",
+                }),
+            },
+        )
+    }
+
+    /// Makes issue `number` `null` on pages of issues, with an error about it
+    /// like GitHub returns for issues that cannot be accessed.
+    pub fn with_unavailable_issue(mut self, number: u64) -> Self {
+        self.unavailable.push(number);
+        self
+    }
+
+    /// Deletes issue `number` before the n-th request, which shifts the
+    /// cursors of newer issues like deletions and transfers do on GitHub.
+    pub fn with_deleted_issue(mut self, request_index: usize, number: u64) -> Self {
+        self.deletions.push((request_index, number));
+        self
+    }
+
+    pub fn start(mut self) -> MockServer {
+        MockServer::start(move |request| self.respond(request))
+    }
+
+    fn respond(&mut self, request: &serde_json::Value) -> Response {
+        let index = self.handled_requests;
+        self.handled_requests += 1;
+        for (_, number) in self.deletions.iter().filter(|(i, _)| *i == index) {
+            self.issues.retain(|issue| issue["number"] != *number);
+        }
+        if let Some(response) = self.overrides.get(&index) {
+            return response.clone();
+        }
+
+        let query = request["query"].as_str().unwrap_or_default();
+        let variables = &request["variables"];
+        let mut errors = vec![];
+        let mut data = if query.contains("resetAt") {
+            serde_json::json!({
+                "rateLimit": {
+                    "limit": 5000,
+                    "remaining": 4999,
+                    "resetAt": "2023-11-01T00:00:00Z",
+                },
+                "repository": { "issues": { "totalCount": self.issues.len() } },
+            })
+        } else if query.contains("issues(") {
+            let mut issues = self.issues_page(variables);
+            for (index, node) in issues["nodes"]
+                .as_array_mut()
+                .unwrap()
+                .iter_mut()
+                .enumerate()
+            {
+                if self
+                    .unavailable
+                    .iter()
+                    .any(|number| node["number"] == *number)
+                {
+                    *node = serde_json::Value::Null;
+                    errors.push(serde_json::json!({
+                        "type": "FORBIDDEN",
+                        "path": ["repository", "issues", "nodes", index],
+                        "message": "Resource not accessible by integration",
+                    }));
+                }
+            }
+            serde_json::json!({ "repository": { "issues": issues } })
+        } else if query.contains("labels(first: $label_page_size, after: $after)") {
+            serde_json::json!({ "repository": { "issue": { "labels": self.labels(variables) } } })
+        } else if query.contains("issue(number: $number)") {
+            serde_json::json!({ "repository": { "issue": self.issue(variables) } })
+        } else {
+            panic!("unexpected query: {query}");
+        };
+        if query.contains("rateLimit") {
+            data["rateLimit"]["cost"] = serde_json::json!(1);
+        }
+        if errors.is_empty() {
+            serde_json::json!({ "data": data }).into()
+        } else {
+            serde_json::json!({ "data": data, "errors": errors }).into()
+        }
+    }
+
+    /// Like `issues(last: $page_size, before: $before)`.
+    fn issues_page(&self, variables: &serde_json::Value) -> serde_json::Value {
+        let page_size = variables["page_size"].as_u64().unwrap() as usize;
+        let end = cursor_index(&variables["before"], "issue-").unwrap_or(self.issues.len());
+        let start = end.saturating_sub(page_size);
+
+        let nodes: Vec<_> = self.issues[start..end]
+            .iter()
+            .map(|issue| with_timeline_page(issue, variables, 0))
+            .collect();
+        serde_json::json!({
+            "totalCount": self.issues.len(),
+            "nodes": nodes,
+            "pageInfo": {
+                "startCursor": format!("issue-{start}"),
+                "hasPreviousPage": start > 0,
+                "endCursor": format!("issue-{}", end.saturating_sub(1)),
+                "hasNextPage": end < self.issues.len(),
+            },
+        })
+    }
+
+    /// Like `issue(number: $number)` with `timelineItems(after: $after)`.
+    fn issue(&self, variables: &serde_json::Value) -> serde_json::Value {
+        let issue = self
+            .issues
+            .iter()
+            .find(|issue| issue["number"] == variables["number"])
+            .expect("issue exists");
+        let start = cursor_index(&variables["after"], "item-").map_or(0, |after| after + 1);
+        with_timeline_page(issue, variables, start)
+    }
+
+    /// Like `issue(number: $number)` with `labels(after: $after)`.
+    fn labels(&self, variables: &serde_json::Value) -> serde_json::Value {
+        let issue = self
+            .issues
+            .iter()
+            .find(|issue| issue["number"] == variables["number"])
+            .expect("issue exists");
+        let start = cursor_index(&variables["after"], "label-").map_or(0, |after| after + 1);
+        labels_page(issue, variables, start)
+    }
+}
+
+/// A query with two issues and two timeline items per page, so that the
+/// fixtures need several pages of both.
+pub fn query(pages: usize) -> IssuesQuery {
+    IssuesQuery::new(
+        serde_json::json!({
+            "page_size": 2,
+            "timeline_page_size": 2,
+            "timelineItemTypes": TIMELINE_ITEM_TYPES,
+        }),
+        pages,
+    )
+}
+
+/// The numbers of the handled issues, with `|` after each page.
+pub async fn handled(github: &GitHub, query: &IssuesQuery) -> anyhow::Result<String> {
+    let handled = std::cell::RefCell::new(String::new());
+    github
+        .for_issues_with_timeline(
+            query,
+            |issue| handled.borrow_mut().push_str(&issue.number.to_string()),
+            || handled.borrow_mut().push('|'),
+        )
+        .await?;
+    Ok(handled.into_inner())
+}
+
+/// A page of the labels of `issue`, starting at `start`.
+fn labels_page(
+    issue: &serde_json::Value,
+    variables: &serde_json::Value,
+    start: usize,
+) -> serde_json::Value {
+    let page_size = variables["label_page_size"].as_u64().unwrap_or(100) as usize;
+    let labels = issue["labels"]["nodes"].as_array().unwrap();
+    let end = (start + page_size).min(labels.len());
+    serde_json::json!({
+        "nodes": labels[start..end],
+        "pageInfo": {
+            "endCursor": format!("label-{}", end.saturating_sub(1)),
+            "hasNextPage": end < labels.len(),
+        },
+    })
+}
+
+/// `issue` with a page of the timeline items of the requested types, starting
+/// at `start`.
+fn with_timeline_page(
+    issue: &serde_json::Value,
+    variables: &serde_json::Value,
+    start: usize,
+) -> serde_json::Value {
+    let page_size = variables["timeline_page_size"].as_u64().unwrap_or(200) as usize;
+    let item_types = variables["timelineItemTypes"].as_array().unwrap();
+    let items: Vec<_> = issue["timelineItems"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|item| {
+            let item_type = item_type(item["__typename"].as_str().unwrap());
+            item_types.iter().any(|t| *t == item_type)
+        })
+        .collect();
+    let end = (start + page_size).min(items.len());
+
+    let mut issue = issue.clone();
+    issue["labels"] = labels_page(&issue, variables, 0);
+    issue["timelineItems"] = serde_json::json!({
+        "nodes": items[start..end],
+        "pageInfo": {
+            "startCursor": format!("item-{start}"),
+            "hasPreviousPage": start > 0,
+            "endCursor": format!("item-{}", end.saturating_sub(1)),
+            "hasNextPage": end < items.len(),
+        },
+    });
+    issue
+}
+
+/// `LabeledEvent` is of type `LABELED_EVENT`.
+fn item_type(typename: &str) -> String {
+    let mut item_type = String::new();
+    for (i, c) in typename.chars().enumerate() {
+        if c.is_uppercase() && i > 0 {
+            item_type.push('_');
+        }
+        item_type.push(c.to_ascii_uppercase());
+    }
+    item_type
+}
+
+fn cursor_index(cursor: &serde_json::Value, prefix: &str) -> Option<usize> {
+    cursor.as_str()?.strip_prefix(prefix)?.parse().ok()
+}
+
+/// The headers and the JSON body of a request.
+fn read_request(stream: &TcpStream) -> Option<(Headers, serde_json::Value)> {
+    let mut reader = BufReader::new(stream);
+    let mut headers = vec![];
+    let mut content_length = 0;
+    loop {
+        let mut line = String::new();
+        reader.read_line(&mut line).ok()?;
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse().ok()?;
+            }
+            headers.push((name.to_owned(), value.trim().to_owned()));
+        }
+    }
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body).ok()?;
+    Some((headers, serde_json::from_slice(&body).ok()?))
+}
+
+fn write_response(mut stream: &TcpStream, response: &Response) {
+    let body = response.body.to_string();
+    let _ = write!(
+        stream,
+        "HTTP/1.1 {} Mock\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        response.status,
+        body.len()
+    );
+}
@@ -0,0 +1,411 @@
+mod mock;
+
+use chrono::{DateTime, Utc};
+use mock::{handled, query, MockGitHub};
+use rust_issue_stats::*;
+
+#[tokio::test]
+async fn test_paging() {
+    let server = MockGitHub::from_fixture("issues.json").start();
+
+    assert_eq!(
+        handled(&server.github(), &query(10)).await.unwrap(),
+        "45|23|1|"
+    );
+
+    let requests = server.requests();
+    // Three pages of issues, and the rest of the timeline of issue 2.
+    assert_eq!(requests.len(), 4);
+    assert_eq!(requests[1]["variables"]["before"], "issue-3");
+    assert_eq!(requests[2]["variables"]["number"], 2);
+    assert_eq!(requests[2]["variables"]["after"], "item-1");
+    assert_eq!(requests[3]["variables"]["before"], "issue-1");
+}
+
+/// Records the progress updates.
+#[derive(Clone, Default)]
+struct Updates(std::sync::Arc<std::sync::Mutex<Vec<Progress>>>);
+
+impl ProgressHook for Updates {
+    fn update(&self, progress: &Progress) {
+        self.0.lock().unwrap().push(progress.clone());
+    }
+}
+
+#[tokio::test]
+async fn test_progress() {
+    let server = MockGitHub::from_fixture("issues.json").start();
+    let updates = Updates::default();
+
+    let github = server.github().with_progress(updates.clone());
+    assert_eq!(handled(&github, &query(10)).await.unwrap(), "45|23|1|");
+
+    let updates = updates.0.lock().unwrap();
+    let summary: Vec<_> = updates
+        .iter()
+        .map(|p| (p.issues, p.pages, p.api_usage.requests))
+        .collect();
+    // After each issue and each page. Issue 2 needs another request.
+    assert_eq!(
+        summary,
+        [
+            (1, 0, 1),
+            (2, 0, 1),
+            (2, 1, 1),
+            (3, 1, 3),
+            (4, 1, 3),
+            (4, 2, 3),
+            (5, 2, 4),
+            (5, 3, 4),
+        ]
+    );
+    // 5 issues on 3 pages of 2.
+    assert!(updates
+        .iter()
+        .all(|p| p.expected_issues == Some(5) && p.expected_pages == Some(3)));
+    assert_eq!(
+        updates.last().unwrap().eta(),
+        Some(std::time::Duration::ZERO)
+    );
+}
+
+#[tokio::test]
+async fn test_collect_pages() {
+    let server = MockGitHub::from_fixture("issues.json").start();
+
+    let mut timelines = vec![];
+    server
+        .github()
+        .for_issues_with_timeline(
+            &query(2),
+            |issue| {
+                if issue.number == 2 {
+                    timelines.push(issue.timeline_items.clone());
+                }
+            },
+            || {},
+        )
+        .await
+        .unwrap();
+
+    let types: Vec<_> = timelines[0].iter().map(TimelineItem::item_type).collect();
+    assert_eq!(
+        types,
+        [
+            "LABELED_EVENT",
+            "ISSUE_COMMENT",
+            "UNLABELED_EVENT",
+            "CLOSED_EVENT"
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_pages_and_created_since() {
+    let server = MockGitHub::from_fixture("issues.json").start();
+    assert_eq!(handled(&server.github(), &query(1)).await.unwrap(), "45|");
+
+    let mut since = query(10);
+    since.created_since = Some("2020-02-15T00:00:00Z".parse::<DateTime<Utc>>().unwrap());
+    let server = MockGitHub::from_fixture("issues.json").start();
+    // All issues on the second page are skipped, and no third page is fetched.
+    assert_eq!(handled(&server.github(), &since).await.unwrap(), "45||");
+    assert_eq!(server.requests().len(), 2);
+}
+
+#[tokio::test]
+async fn test_created_until_before_first_page() {
+    let mut until = query(1);
+    until.created_until = Some("2020-01-15T00:00:00Z".parse::<DateTime<Utc>>().unwrap());
+    let server = MockGitHub::from_fixture("issues.json").start();
+    // Like `--as-of` long ago: the first page with issues 4 and 5 does not
+    // count, and the one page is the one with issue 2.
+    assert_eq!(handled(&server.github(), &until).await.unwrap(), "|2|");
+    // Two pages of issues, and the rest of the timeline of issue 2.
+    assert_eq!(server.requests().len(), 3);
+}
+
+#[tokio::test]
+async fn test_graphql_error() {
+    let server = MockGitHub::from_fixture("issues.json")
+        .with_error(
+            1,
+            "Could not resolve to a Repository with the name 'rust-lang/rust'.",
+        )
+        .start();
+
+    let error = handled(&server.github(), &query(10)).await.unwrap_err();
+    assert!(format!("{error:#}").contains("Could not resolve"));
+}
+
+#[tokio::test]
+async fn test_label_pages() {
+    let server = MockGitHub::from_fixture("issues.json").start();
+    let mut query = query(10);
+    query.variables["label_page_size"] = 2.into();
+
+    let mut labels = vec![];
+    server
+        .github()
+        .for_issues_with_timeline(
+            &query,
+            |issue| {
+                if issue.number == 3 {
+                    labels.push(issue.labels.clone());
+                }
+            },
+            || {},
+        )
+        .await
+        .unwrap();
+
+    let names: Vec<_> = labels[0].nodes.iter().map(|l| l.name.as_str()).collect();
+    assert_eq!(names, ["C-bug", "O-linux", "T-compiler"]);
+    assert_eq!(labels[0].complete, Some(true));
+
+    let requests = server.requests();
+    let label_requests: Vec<_> = requests
+        .iter()
+        .filter(|r| {
+            r["query"]
+                .as_str()
+                .unwrap()
+                .contains("labels(first: $label_page_size, after")
+        })
+        .collect();
+    assert_eq!(label_requests.len(), 1);
+    assert_eq!(label_requests[0]["variables"]["number"], 3);
+    assert_eq!(label_requests[0]["variables"]["after"], "label-1");
+    assert_eq!(label_requests[0]["variables"]["label_page_size"], 2);
+}
+
+#[tokio::test]
+async fn test_unavailable_issue_on_page() {
+    let server = MockGitHub::from_fixture("issues.json")
+        .with_unavailable_issue(4)
+        .start();
+
+    let handled = std::cell::RefCell::new(String::new());
+    let report = server
+        .github()
+        .for_issues_with_timeline(
+            &query(10),
+            |issue| handled.borrow_mut().push_str(&issue.number.to_string()),
+            || handled.borrow_mut().push('|'),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(handled.into_inner(), "5|23|1|");
+    assert_eq!(
+        report.skipped,
+        [SkippedIssue {
+            number: None,
+            errors: vec!["Resource not accessible by integration".to_owned()],
+        }]
+    );
+}
+
+#[tokio::test]
+async fn test_transferred_issue() {
+    // Issue 2 is transferred before the rest of its timeline is fetched.
+    let server = MockGitHub::from_fixture("issues.json")
+        .with_response(
+            2,
+            serde_json::json!({
+                "data": { "rateLimit": { "cost": 1 }, "repository": { "issue": null } },
+                "errors": [{
+                    "type": "NOT_FOUND",
+                    "path": ["repository", "issue"],
+                    "message": "Could not resolve to an issue or pull request with the number of 2.",
+                }],
+            })
+            .into(),
+        )
+        .start();
+
+    let mut handled = vec![];
+    let report = server
+        .github()
+        .for_issues_with_timeline(&query(10), |issue| handled.push(issue.number), || {})
+        .await
+        .unwrap();
+
+    assert_eq!(handled, [4, 5, 3, 1]);
+    assert_eq!(report.skipped.len(), 1);
+    assert_eq!(report.skipped[0].number, Some(2));
+    assert_eq!(
+        report.skipped[0].to_string(),
+        "#2: Could not resolve to an issue or pull request with the number of 2."
+    );
+}
+
+#[tokio::test]
+async fn test_cursor_drift() {
+    // Deleting issue 1 moves issue 4 onto the second page too.
+    let server = MockGitHub::from_fixture("issues.json")
+        .with_deleted_issue(1, 1)
+        .start();
+
+    let handled = std::cell::RefCell::new(String::new());
+    let report = server
+        .github()
+        .for_issues_with_timeline(
+            &query(10),
+            |issue| handled.borrow_mut().push_str(&issue.number.to_string()),
+            || handled.borrow_mut().push('|'),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(handled.into_inner(), "45|3|2|");
+    assert_eq!(report.duplicates, [4]);
+    assert!(report.out_of_order.is_empty());
+    assert_eq!(
+        report.gaps,
+        [PagingGap::Unseen {
+            total_count: 5,
+            seen: 4
+        }]
+    );
+}
+
+#[tokio::test]
+async fn test_clean_paging_report() {
+    let server = MockGitHub::from_fixture("issues.json").start();
+
+    let report = server
+        .github()
+        .for_issues_with_timeline(&query(10), |_| {}, || {})
+        .await
+        .unwrap();
+    assert!(report.is_clean(), "{report}");
+}
+
+const TOO_HEAVY: &str = "Something went wrong while executing your query. This may be the result of a timeout, or it could be a GitHub bug.";
+
+#[tokio::test]
+async fn test_adaptive_page_size() {
+    let server = MockGitHub::from_fixture("issues.json")
+        .with_error(1, TOO_HEAVY)
+        .start();
+
+    assert_eq!(
+        handled(&server.github(), &query(10)).await.unwrap(),
+        "45|3|2|1|"
+    );
+
+    let sizes: Vec<_> = server
+        .requests()
+        .iter()
+        .map(|request| {
+            let variables = &request["variables"];
+            (
+                variables["page_size"].clone(),
+                variables["timeline_page_size"].clone(),
+            )
+        })
+        .collect();
+    let json = serde_json::Value::from;
+    assert_eq!(
+        sizes,
+        [
+            (json(2), json(2)),
+            (json(2), json(2)),
+            // Retried with half the page sizes. They stay small for the next
+            // page, and the timeline of issue 2, since the retry was needed.
+            (json(1), json(1)),
+            (json(1), json(1)),
+            (serde_json::Value::Null, json(1)),
+            (serde_json::Value::Null, json(1)),
+            (serde_json::Value::Null, json(1)),
+            (json(2), json(2)),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_adaptive_page_size_gives_up() {
+    let server = mock::MockServer::start(|_| {
+        serde_json::json!({ "data": null, "errors": [{ "message": TOO_HEAVY }] }).into()
+    });
+
+    let error = handled(&server.github(), &query(10)).await.unwrap_err();
+    assert!(format!("{error:#}").contains("Something went wrong"));
+    // Until both page sizes of 2 are 1.
+    assert_eq!(server.requests().len(), 2);
+}
+
+#[tokio::test]
+async fn test_rate_limit() {
+    let server = MockGitHub::from_fixture("issues.json")
+        .with_rate_limit(0)
+        .start();
+
+    let error = handled(&server.github(), &query(10)).await.unwrap_err();
+    assert!(format!("{error:#}").contains("rate limit"));
+}
+
+#[tokio::test]
+async fn test_cache() {
+    let cache_dir =
+        std::env::temp_dir().join(format!("rust-issue-stats-cache-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&cache_dir);
+
+    let server = MockGitHub::from_fixture("issues.json").start();
+    let github = server.github().with_cache_dir(cache_dir.clone());
+    assert_eq!(handled(&github, &query(10)).await.unwrap(), "45|23|1|");
+    assert_eq!(handled(&github, &query(10)).await.unwrap(), "45|23|1|");
+    assert_eq!(server.requests().len(), 4);
+
+    // Errors are not cached.
+    let server = MockGitHub::from_fixture("issues.json")
+        .with_error(0, "Timeout")
+        .start();
+    let github = server.github().with_cache_dir(cache_dir.join("errors"));
+    assert!(handled(&github, &query(1)).await.is_err());
+    assert_eq!(handled(&github, &query(1)).await.unwrap(), "45|");
+
+    std::fs::remove_dir_all(&cache_dir).unwrap();
+}
+
+#[tokio::test]
+async fn test_timeout() {
+    let server = mock::MockServer::start(|_| {
+        std::thread::sleep(std::time::Duration::from_millis(500));
+        serde_json::json!({ "data": null }).into()
+    });
+    let github = server
+        .github_builder()
+        .timeout(std::time::Duration::from_millis(50))
+        .build()
+        .unwrap();
+
+    let error = handled(&github, &query(1)).await.unwrap_err();
+    assert_eq!(
+        error.downcast_ref::<RequestTimeout>(),
+        Some(&RequestTimeout(std::time::Duration::from_millis(50)))
+    );
+}
+
+#[tokio::test]
+async fn test_user_agent() {
+    let server = MockGitHub::from_fixture("issues.json").start();
+    handled(&server.github(), &query(1)).await.unwrap();
+    let github = server
+        .github_builder()
+        .user_agent("issue-stats-test".to_owned())
+        .build()
+        .unwrap();
+    handled(&github, &query(1)).await.unwrap();
+
+    // Replaced, not sent in addition to the default.
+    assert_eq!(
+        server.header_values("user-agent"),
+        [vec!["octocrab"], vec!["issue-stats-test"]]
+    );
+    assert_eq!(
+        server.header_values("authorization"),
+        [vec!["Bearer mock-token"], vec!["Bearer mock-token"]]
+    );
+}